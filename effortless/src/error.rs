@@ -111,10 +111,33 @@ impl APIError {
         }
     }
 
+    /// Sets the id of the error, unless it already has one.
+    pub fn with_default_id(mut self, id: Option<String>) -> Self {
+        if self.id.is_none() {
+            self.id = id;
+        }
+        self
+    }
+
     /// Returns the APIError status.
     pub fn status(&self) -> String {
         self.status.clone()
     }
+
+    /// Returns the APIError title.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the APIError details.
+    pub fn details(&self) -> &str {
+        &self.details
+    }
+
+    /// Returns the APIError source.
+    pub fn source(&self) -> Option<&APIErrorSource> {
+        self.source.as_ref()
+    }
 }
 
 impl From<APIError> for Response<Body> {
@@ -165,6 +188,56 @@ impl APIErrors {
     }
 }
 
+impl APIErrors {
+    /// Returns the HTTP status code of the errors.
+    ///
+    /// If there is only one error, the status code will be the same as the one of the
+    /// error. Otherwise it will be set to [StatusCode::BAD_REQUEST].
+    pub fn status_code(&self) -> StatusCode {
+        match self.errors.as_slice() {
+            [error] => error
+                .status
+                .parse::<StatusCode>()
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Converts the errors to an RFC 9457 problem details object.
+    ///
+    /// The problems are only specified by their status code, therefore their type is
+    /// `about:blank`, to be refined by the caller, and their title the reason phrase of
+    /// the status code. Each error is reported in the `errors` extension, with the part
+    /// of the request it points to.
+    ///
+    /// `instance` is the URI reference of the request the problem occurred on.
+    pub fn to_problem_details(&self, instance: Option<&str>) -> ProblemDetails {
+        let status = self.status_code();
+        let detail = match self.errors.as_slice() {
+            [error] => error.details.clone(),
+            errors => format!("the request contains {} errors", errors.len()),
+        };
+        let errors = (!self.errors.is_empty()).then(|| {
+            self.errors
+                .iter()
+                .map(|e| ProblemFieldError {
+                    detail: e.details.clone(),
+                    source: e.source.clone(),
+                })
+                .collect()
+        });
+        ProblemDetails {
+            problem_type: String::from(PROBLEM_TYPE),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail,
+            instance: instance.map(String::from),
+            request_id: self.errors.first().and_then(|e| e.id.clone()),
+            errors,
+        }
+    }
+}
+
 impl From<APIError> for APIErrors {
     fn from(value: APIError) -> Self {
         APIErrors {
@@ -191,3 +264,101 @@ impl From<APIErrors> for Response<Body> {
             .unwrap()
     }
 }
+
+/// Media type of the RFC 9457 problem details documents.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Type of the problems which have no semantics beyond their HTTP status code, as
+/// defined by RFC 9457.
+pub const PROBLEM_TYPE: &str = "about:blank";
+
+/// Problem details object as described in <https://www.rfc-editor.org/rfc/rfc9457>.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// A short, human-readable summary of the problem type.
+    pub title: String,
+    /// The HTTP status code generated by the origin server for this occurrence.
+    pub status: u16,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    pub detail: String,
+    /// A URI reference identifying the specific occurrence of the problem.
+    pub instance: Option<String>,
+    /// Extension: the identifier of the request the problem occurred on.
+    pub request_id: Option<String>,
+    /// Extension: the errors of the request, with the parts of the request they point
+    /// to.
+    pub errors: Option<Vec<ProblemFieldError>>,
+}
+
+/// Error of an individual part of the request, reported in the `errors` extension
+/// of a [`ProblemDetails`].
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ProblemFieldError {
+    /// A human-readable explanation of the error.
+    pub detail: String,
+    /// The part of the request which caused the error.
+    #[serde(flatten)]
+    pub source: Option<APIErrorSource>,
+}
+
+impl From<ProblemDetails> for Response<Body> {
+    /// Converts this object to a `Response<Body>`.
+    fn from(value: ProblemDetails) -> Self {
+        Response::builder()
+            .status(value.status)
+            .header(header::CONTENT_TYPE, PROBLEM_JSON)
+            .body(json!(value).to_string().into())
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_details_with_field_errors() {
+        let errors = APIErrors::new(&[
+            APIError::with_pointer(Some("rid".into()), "/email", "invalid email"),
+            APIError::with_pointer(Some("rid".into()), "/consent", "consent is required"),
+        ]);
+        let problem = errors.to_problem_details(Some("/cities/submissions"));
+        assert_eq!(
+            json!(problem),
+            json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "the request contains 2 errors",
+                "instance": "/cities/submissions",
+                "request_id": "rid",
+                "errors": [
+                    {"detail": "invalid email", "pointer": "/email"},
+                    {"detail": "consent is required", "pointer": "/consent"}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_problem_details_with_single_error() {
+        let errors: APIErrors = APIError::not_found(None, "/cities/1", "no city").into();
+        let problem = errors.to_problem_details(Some("/cities/1"));
+        assert_eq!(problem.problem_type, "about:blank");
+        assert_eq!(problem.status, 404);
+        assert_eq!(problem.title, "Not Found");
+        assert_eq!(problem.detail, "no city");
+        assert_eq!(
+            problem.errors,
+            Some(vec![ProblemFieldError {
+                detail: String::from("no city"),
+                source: Some(APIErrorSource::Pointer(String::from("/cities/1"))),
+            }])
+        );
+    }
+}
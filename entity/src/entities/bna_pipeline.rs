@@ -17,6 +17,8 @@ pub struct Model {
     pub start_time: DateTimeWithTimeZone,
     pub end_time: Option<DateTimeWithTimeZone>,
     pub cost: Option<Decimal>,
+    pub status_reason: Option<String>,
+    pub updated_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -45,6 +47,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     FargatePrice,
+    #[sea_orm(has_many = "super::summary::Entity")]
+    Summary,
 }
 
 impl Related<super::bna_pipeline_status::Entity> for Entity {
@@ -65,4 +69,10 @@ impl Related<super::fargate_price::Entity> for Entity {
     }
 }
 
+impl Related<super::summary::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Summary.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -20,6 +20,10 @@ pub mod recreation;
 pub mod retail;
 pub mod state_region_crosswalk;
 pub mod submission;
+pub mod submission_review;
 pub mod summary;
 pub mod transit;
 pub mod us_state;
+pub mod webhook_delivery_status;
+pub mod webhook_outbox;
+pub mod webhook_subscription;
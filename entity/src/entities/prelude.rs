@@ -18,6 +18,10 @@ pub use super::recreation::Entity as Recreation;
 pub use super::retail::Entity as Retail;
 pub use super::state_region_crosswalk::Entity as StateRegionCrosswalk;
 pub use super::submission::Entity as Submission;
+pub use super::submission_review::Entity as SubmissionReview;
 pub use super::summary::Entity as Summary;
 pub use super::transit::Entity as Transit;
 pub use super::us_state::Entity as UsState;
+pub use super::webhook_delivery_status::Entity as WebhookDeliveryStatus;
+pub use super::webhook_outbox::Entity as WebhookOutbox;
+pub use super::webhook_subscription::Entity as WebhookSubscription;
@@ -40,6 +40,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Country,
+    #[sea_orm(has_many = "super::submission_review::Entity")]
+    SubmissionReview,
 }
 
 impl Related<super::approval_status::Entity> for Entity {
@@ -54,4 +56,10 @@ impl Related<super::country::Entity> for Entity {
     }
 }
 
+impl Related<super::submission_review::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SubmissionReview.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -16,10 +16,19 @@ pub struct Model {
     #[sea_orm(column_type = "Double")]
     pub score: f64,
     pub version: String,
+    pub pipeline_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::bna_pipeline::Entity",
+        from = "Column::PipelineId",
+        to = "super::bna_pipeline::Column::StateMachineId",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    BnaPipeline,
     #[sea_orm(
         belongs_to = "super::city::Entity",
         from = "Column::CityId",
@@ -46,6 +55,12 @@ pub enum Relation {
     Transit,
 }
 
+impl Related<super::bna_pipeline::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BnaPipeline.def()
+    }
+}
+
 impl Related<super::city::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::City.def()
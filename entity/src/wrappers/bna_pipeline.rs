@@ -35,7 +35,9 @@ impl IntoActiveModel<bna_pipeline::ActiveModel> for BNAPipelinePost {
             ),
             state_machine_id: ActiveValue::Set(self.state_machine_id),
             status: ActiveValue::Set("Pending".to_string()),
+            status_reason: ActiveValue::NotSet,
             step: ActiveValue::Set("Setup".to_string()),
+            updated_at: ActiveValue::NotSet,
         }
     }
 }
@@ -51,6 +53,7 @@ pub struct BNAPipelinePatch {
     pub sqs_message: Option<Option<Json>>,
     pub start_time: Option<Option<DateTimeWithTimeZone>>,
     pub status: Option<String>,
+    pub status_reason: Option<Option<String>>,
     pub step: Option<String>,
     pub torn_down: Option<Option<bool>>,
 }
@@ -75,6 +78,12 @@ impl IntoActiveModel<bna_pipeline::ActiveModel> for BNAPipelinePatch {
                 .fargate_price_id
                 .map_or(ActiveValue::NotSet, ActiveValue::Set),
             status: self.status.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            status_reason: self
+                .status_reason
+                .map_or(ActiveValue::NotSet, ActiveValue::Set),
+            updated_at: ActiveValue::Set(Some(
+                Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
+            )),
         }
     }
 }
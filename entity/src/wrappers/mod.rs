@@ -2,6 +2,7 @@ pub mod bna;
 pub mod bna_pipeline;
 pub mod city;
 pub mod submission;
+pub mod webhook_subscription;
 
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
@@ -21,11 +22,36 @@ impl FromStr for ApprovalStatus {
     }
 }
 
+impl Display for ApprovalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
+}
+
+impl ApprovalStatus {
+    /// Returns `true` if a submission can be reviewed from this status to `next`.
+    ///
+    /// Pending submissions can be approved or rejected, and rejected ones can be
+    /// reconsidered and approved. Approving a submission queues its analysis, so an
+    /// approval is final.
+    pub fn can_transition_to(&self, next: &ApprovalStatus) -> bool {
+        matches!(
+            (self, next),
+            (ApprovalStatus::Pending, ApprovalStatus::Approved)
+                | (ApprovalStatus::Pending, ApprovalStatus::Rejected)
+                | (ApprovalStatus::Rejected, ApprovalStatus::Approved)
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BNAPipelineStatus {
     Pending,
     Processing,
     Completed,
+    Failed,
+    TimedOut,
 }
 
 impl FromStr for BNAPipelineStatus {
@@ -36,6 +62,13 @@ impl FromStr for BNAPipelineStatus {
     }
 }
 
+impl Display for BNAPipelineStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BNAPipelineStep {
     Analysis,
@@ -52,6 +85,13 @@ impl FromStr for BNAPipelineStep {
     }
 }
 
+impl Display for BNAPipelineStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BnaRegion {
     #[serde(rename = "Mid-Atlantic")]
@@ -104,4 +144,64 @@ mod tests {
         );
         assert_eq!(BnaRegion::South.to_string(), String::from("South"));
     }
+
+    #[test]
+    fn test_approval_status_transitions() {
+        use ApprovalStatus::*;
+        assert!(Pending.can_transition_to(&Approved));
+        assert!(Pending.can_transition_to(&Rejected));
+        assert!(Rejected.can_transition_to(&Approved));
+        assert!(!Approved.can_transition_to(&Rejected));
+        assert!(!Approved.can_transition_to(&Approved));
+        assert!(!Rejected.can_transition_to(&Rejected));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "pipeline.completed")]
+    PipelineCompleted,
+    #[serde(rename = "pipeline.failed")]
+    PipelineFailed,
+    #[serde(rename = "rating.created")]
+    RatingCreated,
+    #[serde(rename = "submission.status_changed")]
+    SubmissionStatusChanged,
+}
+
+impl FromStr for WebhookEvent {
+    type Err = serde_plain::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_plain::from_str::<Self>(s)
+    }
+}
+
+impl Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl FromStr for WebhookDeliveryStatus {
+    type Err = serde_plain::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_plain::from_str::<Self>(s)
+    }
+}
+
+impl Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
 }
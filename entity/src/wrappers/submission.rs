@@ -1,4 +1,4 @@
-use crate::entities::submission;
+use crate::entities::{submission, submission_review};
 use sea_orm::{ActiveValue, IntoActiveModel};
 use serde::{Deserialize, Serialize};
 
@@ -78,6 +78,29 @@ impl IntoActiveModel<submission::ActiveModel> for SubmissionPatch {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionReviewPost {
+    pub submission_id: i32,
+    pub reviewer: String,
+    pub previous_status: String,
+    pub status: String,
+    pub note: Option<String>,
+}
+
+impl IntoActiveModel<submission_review::ActiveModel> for SubmissionReviewPost {
+    fn into_active_model(self) -> submission_review::ActiveModel {
+        submission_review::ActiveModel {
+            id: ActiveValue::NotSet,
+            submission_id: ActiveValue::Set(self.submission_id),
+            reviewer: ActiveValue::Set(self.reviewer),
+            previous_status: ActiveValue::Set(self.previous_status),
+            status: ActiveValue::Set(self.status),
+            note: ActiveValue::Set(self.note),
+            created_at: ActiveValue::NotSet,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,86 @@
+use super::WebhookEvent;
+use crate::entities::webhook_subscription;
+use sea_orm::{
+    prelude::Json,
+    sqlx::types::chrono::{FixedOffset, Utc},
+    ActiveValue, IntoActiveModel,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSubscriptionPost {
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Vec<WebhookEvent>,
+    pub active: Option<bool>,
+}
+
+impl IntoActiveModel<webhook_subscription::ActiveModel> for WebhookSubscriptionPost {
+    fn into_active_model(self) -> webhook_subscription::ActiveModel {
+        webhook_subscription::ActiveModel {
+            id: ActiveValue::NotSet,
+            url: ActiveValue::Set(self.url),
+            secret: self.secret.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            events: ActiveValue::Set(events_to_json(&self.events)),
+            active: self.active.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            created_at: ActiveValue::NotSet,
+            updated_at: ActiveValue::NotSet,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSubscriptionPatch {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>,
+}
+
+impl IntoActiveModel<webhook_subscription::ActiveModel> for WebhookSubscriptionPatch {
+    fn into_active_model(self) -> webhook_subscription::ActiveModel {
+        webhook_subscription::ActiveModel {
+            id: ActiveValue::NotSet,
+            url: self.url.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            secret: self.secret.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            events: self.events.map_or(ActiveValue::NotSet, |v| {
+                ActiveValue::Set(events_to_json(&v))
+            }),
+            active: self.active.map_or(ActiveValue::NotSet, ActiveValue::Set),
+            created_at: ActiveValue::NotSet,
+            updated_at: ActiveValue::Set(Some(
+                Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
+            )),
+        }
+    }
+}
+
+/// Converts a list of events to the JSON array stored in the database.
+fn events_to_json(events: &[WebhookEvent]) -> Json {
+    Json::Array(events.iter().map(|e| Json::String(e.to_string())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_subscription_post_into_active_model() {
+        let wrapper = WebhookSubscriptionPost {
+            url: "https://example.com/hooks".to_string(),
+            secret: None,
+            events: vec![WebhookEvent::PipelineCompleted, WebhookEvent::RatingCreated],
+            active: None,
+        };
+        let active_model = wrapper.into_active_model();
+        assert_eq!(
+            active_model.events,
+            ActiveValue::Set(Json::Array(vec![
+                Json::String("pipeline.completed".to_string()),
+                Json::String("rating.created".to_string())
+            ]))
+        );
+        assert_eq!(active_model.secret, ActiveValue::NotSet);
+        assert_eq!(active_model.active, ActiveValue::NotSet);
+    }
+}
@@ -190,6 +190,7 @@ async fn main() -> Result<(), Report> {
             },
             population: ActiveValue::Set(scorecard.census_population.try_into().unwrap()),
             residential_speed_limit_override: ActiveValue::NotSet,
+            pipeline_id: ActiveValue::NotSet,
         };
         summaries.push(summary_model);
 
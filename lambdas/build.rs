@@ -0,0 +1,24 @@
+use std::process::Command;
+
+const GIT_SHA_KEY: &str = "BNA_API_GIT_SHA";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed={GIT_SHA_KEY}");
+    if std::env::var_os(GIT_SHA_KEY).is_some() {
+        return;
+    }
+
+    // Fall back to the commit of the working tree when the CI does not provide it.
+    let Ok(output) = Command::new("git").args(["rev-parse", "HEAD"]).output() else {
+        return;
+    };
+    if !output.status.success() {
+        return;
+    }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !sha.is_empty() {
+        println!("cargo:rustc-env={GIT_SHA_KEY}={sha}");
+        println!("cargo:rerun-if-changed=../.git/HEAD");
+        println!("cargo:rerun-if-changed=../.git/refs");
+    }
+}
@@ -0,0 +1,47 @@
+use lambdas::core::openapi::{check_specifications, routes, write_specifications};
+use std::{env, path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "usage: openapi-spec [--check] [DIR]";
+
+/// Generates the OpenAPI 3.1 specifications of the API and their OpenAPI 3.0
+/// down-conversion into `DIR`, the current directory by default.
+///
+/// With `--check`, nothing is written: the command fails if the specifications of `DIR`
+/// do not describe the routes anymore.
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut dir = PathBuf::from(".");
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            arg if arg.starts_with('-') => {
+                eprintln!("unknown option {arg}\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            arg => dir = PathBuf::from(arg),
+        }
+    }
+
+    let (_, specifications) = routes();
+    let result = if check {
+        check_specifications(&dir, &specifications)
+            .map(|_| println!("The OpenAPI specifications are up to date."))
+    } else {
+        write_specifications(&dir, &specifications).map(|paths| {
+            for path in paths {
+                println!("Wrote {}", path.display());
+            }
+        })
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
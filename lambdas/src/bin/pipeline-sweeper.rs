@@ -0,0 +1,82 @@
+use dotenvy::dotenv;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use lambdas::{
+    core::{
+        config::{self, Config},
+        metrics,
+        resource::{
+            pipelines::{adaptor::sweep_stale_pipelines_adaptor, StaleThresholds},
+            system::adaptor::refresh_domain_gauges,
+        },
+    },
+    database_connect,
+};
+use serde_json::{json, Value};
+use std::env;
+use tracing::{info, warn};
+
+const USAGE: &str = "usage: pipeline-sweeper [sweep]";
+
+/// Marks the pipelines which stopped progressing as failed or timed out, then
+/// refreshes the domain gauges.
+async fn sweep() -> Result<Value, Error> {
+    let db = database_connect().await?;
+    let thresholds = StaleThresholds::from(&config::config().pipelines);
+    let swept = sweep_stale_pipelines_adaptor(&db, &thresholds).await?;
+    info!("{} stale pipeline(s) swept", swept.len());
+    if let Err(e) = refresh_domain_gauges(&db).await {
+        warn!("cannot refresh the domain gauges: {e}");
+    }
+
+    Ok(json!({
+        "swept": swept
+            .iter()
+            .map(|p| json!({"state_machine_id": p.state_machine_id, "status": p.status}))
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// Sweeps the stale pipelines.
+///
+/// Meant to be triggered on a schedule, i.e. by an EventBridge rule. The content of
+/// the event is ignored.
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    sweep().await
+}
+
+/// Runs as a Lambda function, or sweeps the stale pipelines once and exits when
+/// invoked with the `sweep` subcommand.
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        // disable printing the name of the module in every log line.
+        .with_target(false)
+        // disabling time is handy because CloudWatch will add the ingestion time.
+        .without_time()
+        .init();
+
+    config::init(Config::load()?);
+
+    match env::args().nth(1).as_deref() {
+        None => {
+            // The domain gauges are emitted as logs, there is no scraper in Lambda mode.
+            metrics::enable_emf();
+            run(service_fn(function_handler)).await.map_err(|e| {
+                info!("{e}");
+                e
+            })
+        }
+        Some("sweep") => {
+            let report = sweep().await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+        Some("-h" | "--help") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(arg) => Err(format!("unknown argument {arg}\n{USAGE}").into()),
+    }
+}
@@ -6,7 +6,13 @@ use effortless::{
 };
 use entity::wrappers::bna_pipeline::BNAPipelinePatch;
 use lambda_http::{run, service_fn, Body, Error, IntoResponse, Request, Response};
-use lambdas::{core::resource::ratings::adaptor::patch_ratings_analysis_adaptor, Context};
+use lambdas::{
+    core::{
+        config::{self, Config},
+        resource::ratings::adaptor::patch_ratings_analysis_adaptor,
+    },
+    Context,
+};
 use sea_orm::prelude::Uuid;
 use tracing::info;
 
@@ -53,6 +59,9 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
+    dotenv().ok();
+    config::init(Config::load()?);
+
     run(service_fn(function_handler)).await.map_err(|e| {
         info!("{e}");
         e
@@ -0,0 +1,79 @@
+use dotenvy::dotenv;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use lambdas::{
+    core::{
+        config::{self, Config},
+        server::shutdown_signal,
+        webhook::{self, dispatch_pending},
+    },
+    database_connect,
+};
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::info;
+
+/// Delivers the pending webhook events.
+async fn dispatch(db: &DatabaseConnection, client: &reqwest::Client) -> Result<Value, Error> {
+    let report = dispatch_pending(db, client, config::config().webhooks.batch_size).await?;
+    info!(
+        "{} delivered, {} to retry, {} failed",
+        report.delivered, report.retried, report.failed
+    );
+    Ok(json!({
+        "delivered": report.delivered,
+        "retried": report.retried,
+        "failed": report.failed,
+    }))
+}
+
+/// Runs the dispatcher.
+///
+/// In Lambda mode, the function is meant to be triggered on a schedule, i.e. by an
+/// EventBridge rule, and the content of the event is ignored. In standalone mode,
+/// enabled with `BNA_API_STANDALONE=1`, the dispatcher runs every
+/// `webhooks.dispatch_interval` seconds, until `SIGTERM` or `SIGINT` is received.
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        // disable printing the name of the module in every log line.
+        .with_target(false)
+        // disabling time is handy because CloudWatch will add the ingestion time.
+        .without_time()
+        .init();
+
+    let config = config::init(Config::load()?);
+    let db = database_connect().await?;
+    let client = webhook::client()?;
+
+    if config.features.standalone {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(config.webhooks.dispatch_interval));
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("shutting down the webhook dispatcher");
+                    return Ok(());
+                }
+                _ = ticker.tick() => {
+                    if let Err(e) = dispatch(&db, &client).await {
+                        info!("{e}");
+                    }
+                }
+            }
+        }
+    }
+
+    run(service_fn(|_event: LambdaEvent<Value>| {
+        dispatch(&db, &client)
+    }))
+    .await
+    .map_err(|e| {
+        info!("{e}");
+        e
+    })
+}
@@ -0,0 +1,94 @@
+//! Cross-Origin Resource Sharing of the API.
+//!
+//! The browser front-ends hosted on other origins can only call the API if their
+//! origin is allowed by the `cors` configuration.
+use crate::core::config::CorsConfig;
+use axum::http::{HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// Builds the CORS layer, or `None` if no origin is allowed.
+///
+/// The settings are expected to be validated already, the invalid values are ignored.
+pub fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+    let origins: AllowOrigin = if config.allowed_origins.iter().any(|o| o == "*") {
+        Any.into()
+    } else {
+        config
+            .allowed_origins
+            .iter()
+            .filter_map(|o| HeaderValue::from_str(o.trim_end_matches('/')).ok())
+            .collect::<Vec<_>>()
+            .into()
+    };
+    let methods = config
+        .allowed_methods
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.as_bytes()).ok())
+        .collect::<Vec<_>>();
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(header_names(&config.allowed_headers))
+            .expose_headers(header_names(&config.exposed_headers))
+            .max_age(Duration::from_secs(config.max_age)),
+    )
+}
+
+fn header_names(names: &[String]) -> Vec<HeaderName> {
+    names
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, Request},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_cors_disabled_without_origins() {
+        assert!(cors_layer(&CorsConfig::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_exposes_pagination_headers() {
+        let config = CorsConfig {
+            allowed_origins: vec![String::from("https://bna.peopleforbikes.org/")],
+            ..CorsConfig::default()
+        };
+        let app = Router::new()
+            .route("/cities", get(|| async { "[]" }))
+            .layer(cors_layer(&config).unwrap());
+        let request = Request::builder()
+            .uri("/cities")
+            .header(header::ORIGIN, "https://bna.peopleforbikes.org")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://bna.peopleforbikes.org"
+        );
+        let exposed = headers[header::ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap()
+            .split(',')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        assert!(exposed.contains(&"link"));
+        assert!(exposed.contains(&"x-total-pages"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Extractors rejecting the invalid requests with [`APIErrors`].
+//!
+//! They replace the axum extractors of the same name, whose rejections are rendered as
+//! plain text, and point at the part of the request which could not be deserialized.
+use crate::{error_response, Context};
+use axum::{
+    body::Bytes,
+    extract::{rejection::PathRejection, FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use effortless::error::{APIError, APIErrorSource, APIErrors};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_path_to_error::Segment;
+use std::ops::{Deref, DerefMut};
+use url::form_urlencoded;
+
+/// JSON extractor and response.
+///
+/// The body must be sent with a JSON content type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json_content_type(req.headers()) {
+            return Err(reject(APIError::new(
+                request_id(),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Unsupported Media Type",
+                "expected a request body with the `application/json` content type",
+                Some(APIErrorSource::Header(header::CONTENT_TYPE.to_string())),
+            )));
+        }
+        let bytes = Bytes::from_request(req, state).await.map_err(|e| {
+            reject(APIError::new(
+                request_id(),
+                e.status(),
+                "Invalid Request Body",
+                &e.body_text(),
+                None,
+            ))
+        })?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        serde_path_to_error::deserialize(deserializer)
+            .map(Json)
+            .map_err(|e| {
+                let pointer = json_pointer(e.path(), e.inner());
+                let message = format!("invalid request body: {}", e.inner());
+                reject(APIError::with_pointer(request_id(), &pointer, &message))
+            })
+    }
+}
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Query string extractor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        serde_path_to_error::deserialize(deserializer)
+            .map(Query)
+            .map_err(|e| {
+                let parameter = missing_field(&e.inner().to_string())
+                    .or_else(|| e.path().iter().next().map(segment_name))
+                    .unwrap_or_default();
+                let message = format!(
+                    "invalid query string parameter `{parameter}`: {}",
+                    e.inner()
+                );
+                reject(APIError::with_parameter(request_id(), &parameter, &message))
+            })
+    }
+}
+
+/// Path parameters extractor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        use axum::extract::path::ErrorKind;
+
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) => {
+                let parameter = match e.kind() {
+                    ErrorKind::ParseErrorAtKey { key, .. }
+                    | ErrorKind::InvalidUtf8InPathParam { key }
+                    | ErrorKind::DeserializeError { key, .. } => key.clone(),
+                    _ => String::new(),
+                };
+                let message = format!("invalid path parameter `{parameter}`: {}", e.kind());
+                Err(reject(APIError::with_pointer(
+                    request_id(),
+                    &to_pointer([parameter.as_str()]),
+                    &message,
+                )))
+            }
+            Err(e) => Err(reject(APIError::internal_error(
+                request_id(),
+                "Internal Error",
+                &e.body_text(),
+                &Context::current()
+                    .map(|ctx| ctx.source())
+                    .unwrap_or_default(),
+            ))),
+        }
+    }
+}
+
+macro_rules! impl_deref {
+    ($extractor:ident) => {
+        impl<T> Deref for $extractor<T> {
+            type Target = T;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $extractor<T> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+    };
+}
+
+impl_deref!(Json);
+impl_deref!(Query);
+impl_deref!(Path);
+
+/// Renders the rejection of an extractor.
+fn reject(error: APIError) -> Response {
+    error_response(APIErrors::from(error))
+}
+
+/// Returns the identifier of the request being processed.
+fn request_id() -> Option<String> {
+    Context::current().and_then(|ctx| ctx.request_id())
+}
+
+/// Returns `true` if the request declares a JSON body, i.e. `application/json` or
+/// `application/*+json`.
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .is_some_and(|v| {
+            v.strip_prefix("application/")
+                .is_some_and(|subtype| subtype == "json" || subtype.ends_with("+json"))
+        })
+}
+
+/// Builds the JSON pointer (RFC 6901) of the attribute which failed to deserialize.
+///
+/// Serde reports the missing fields on their parent object, so they are appended to
+/// the path.
+fn json_pointer(path: &serde_path_to_error::Path, error: &serde_json::Error) -> String {
+    let mut segments = path
+        .iter()
+        .filter(|segment| !matches!(segment, Segment::Unknown | Segment::Enum { .. }))
+        .map(segment_name)
+        .collect::<Vec<_>>();
+    if let Some(field) = missing_field(&error.to_string()) {
+        segments.push(field);
+    }
+    to_pointer(segments.iter().map(String::as_str))
+}
+
+/// Builds a JSON pointer (RFC 6901) from its segments, escaping them.
+fn to_pointer<'a>(segments: impl IntoIterator<Item = &'a str>) -> String {
+    segments
+        .into_iter()
+        .map(|s| format!("/{}", s.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Returns the name of a path segment.
+fn segment_name(segment: &Segment) -> String {
+    match segment {
+        Segment::Seq { index } => index.to_string(),
+        Segment::Map { key } => key.clone(),
+        Segment::Enum { variant } => variant.clone(),
+        Segment::Unknown => String::new(),
+    }
+}
+
+/// Extracts the name of the field from a serde "missing field" error message.
+fn missing_field(message: &str) -> Option<String> {
+    let field = message.strip_prefix("missing field `")?;
+    field.split_once('`').map(|(name, _)| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    struct Rating {
+        city: String,
+        scores: Vec<Score>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    struct Score {
+        value: f64,
+    }
+
+    fn pointer(body: &str) -> String {
+        let deserializer = &mut serde_json::Deserializer::from_str(body);
+        let e = serde_path_to_error::deserialize::<_, Rating>(deserializer).unwrap_err();
+        json_pointer(e.path(), e.inner())
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        assert_eq!(pointer(r#"{"city": 1, "scores": []}"#), "/city");
+        assert_eq!(
+            pointer(r#"{"city": "x", "scores": [{"value": 1}, {"value": "a"}]}"#),
+            "/scores/1/value"
+        );
+        assert_eq!(pointer(r#"{"scores": []}"#), "/city");
+        assert_eq!(
+            pointer(r#"{"city": "x", "scores": [{}]}"#),
+            "/scores/0/value"
+        );
+        assert_eq!(pointer(r#"{"city": "x""#), "");
+    }
+
+    #[test]
+    fn test_to_pointer() {
+        assert_eq!(to_pointer(["rating_id"]), "/rating_id");
+        assert_eq!(to_pointer(["a/b", "c~d"]), "/a~1b/c~0d");
+        assert_eq!(to_pointer([]), "");
+    }
+
+    #[test]
+    fn test_is_json_content_type() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_json_content_type(&headers));
+        headers.insert(
+            header::CONTENT_TYPE,
+            "application/json; charset=utf-8".parse().unwrap(),
+        );
+        assert!(is_json_content_type(&headers));
+        headers.insert(
+            header::CONTENT_TYPE,
+            "application/merge-patch+json".parse().unwrap(),
+        );
+        assert!(is_json_content_type(&headers));
+        headers.insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        assert!(!is_json_content_type(&headers));
+    }
+}
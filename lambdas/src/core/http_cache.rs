@@ -0,0 +1,333 @@
+//! HTTP caching of the read-only requests.
+//!
+//! The successful responses to the `GET` and `HEAD` requests are tagged with a weak
+//! `ETag`, computed from their payload, and with the `Cache-Control` policy of their
+//! route. The tag is weak since the payload is compressed afterwards, as negotiated
+//! with each client, the compressed representations sharing the tag. The requests
+//! carrying a matching `If-None-Match` header, or an `If-Modified-Since` header not
+//! older than the `Last-Modified` header set by the handler, are answered with
+//! `304 Not Modified` and an empty body.
+//!
+//! The streamed and large payloads, i.e. the CSV reports, are not buffered to be
+//! tagged: they are only validated by their `Last-Modified` header.
+//!
+//! The BNA data changing rarely, this allows CloudFront and the browsers to cache the
+//! responses aggressively.
+use crate::core::versioning::unversioned_route;
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{MatchedPath, Request},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Format of the HTTP dates, as defined by RFC 9110.
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Size above which a payload is not buffered to compute its tag, in bytes.
+const MAX_TAGGED_SIZE: u64 = 1024 * 1024;
+
+/// Policy of the routes without a specific one.
+///
+/// The responses can be stored, but must be revalidated before being reused.
+const DEFAULT_POLICY: &str = "no-cache";
+
+/// `Cache-Control` policies of the routes.
+///
+/// The routes exposing personal data or the state of the service must never be stored.
+const POLICIES: &[(&str, &str)] = &[
+    ("/admin/webhooks", "private, no-store"),
+    ("/admin/webhooks/{webhook_id}", "private, no-store"),
+    ("/cities/submissions", "private, no-store"),
+    ("/cities/submissions/{submission_id}", "private, no-store"),
+    ("/cities/summary/latest", "public, max-age=3600"),
+    ("/cities/top/{year}/{count}", "public, max-age=3600"),
+    ("/health", "no-store"),
+    ("/health/live", "no-store"),
+    ("/health/ready", "no-store"),
+    ("/metrics", "no-store"),
+    ("/ratings/{rating_id}", "public, max-age=86400"),
+    ("/reports", "public, max-age=86400"),
+    ("/reports/{year}", "public, max-age=86400"),
+    ("/usstates", "public, max-age=86400"),
+    ("/usstates/{name}", "public, max-age=86400"),
+];
+
+/// Returns the `Cache-Control` policy of a route, whatever its version.
+pub fn cache_policy(route: &str) -> &'static str {
+    let route = unversioned_route(route);
+    POLICIES
+        .iter()
+        .find(|(r, _)| *r == route)
+        .map_or(DEFAULT_POLICY, |(_, policy)| policy)
+}
+
+/// Formats a date as an HTTP date, i.e. for the `Last-Modified` header.
+pub fn http_date<Tz: TimeZone>(date: &DateTime<Tz>) -> String {
+    date.with_timezone(&Utc)
+        .format(HTTP_DATE_FORMAT)
+        .to_string()
+}
+
+/// Returns the `Last-Modified` header of a resource built from several entries, i.e.
+/// the date of the most recent one, if any.
+pub fn last_modified<Tz: TimeZone>(
+    dates: impl IntoIterator<Item = DateTime<Tz>>,
+) -> Option<(HeaderName, String)> {
+    dates
+        .into_iter()
+        .map(|date| date.with_timezone(&Utc))
+        .max()
+        .map(|date| (header::LAST_MODIFIED, http_date(&date)))
+}
+
+/// Parses an HTTP date, i.e. from the `If-Modified-Since` header.
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// Computes the weak entity tag of a payload.
+fn entity_tag(payload: &[u8]) -> String {
+    let digest = Sha256::digest(payload);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Returns `true` if the `If-None-Match` header matches the entity tag, if any.
+///
+/// The comparison is weak, as required by RFC 9110 for this header.
+fn none_match(if_none_match: &str, etag: Option<&str>) -> bool {
+    if_none_match.split(',').map(str::trim).any(|tag| {
+        tag == "*"
+            || etag
+                .is_some_and(|etag| tag.trim_start_matches("W/") == etag.trim_start_matches("W/"))
+    })
+}
+
+/// Returns `true` if the resource was not modified since the date of the request.
+///
+/// The `If-Modified-Since` header is ignored when `If-None-Match` is present.
+fn not_modified(request: &HeaderMap, response: &HeaderMap, etag: Option<&str>) -> bool {
+    if let Some(if_none_match) = request.get(header::IF_NONE_MATCH) {
+        return if_none_match
+            .to_str()
+            .is_ok_and(|value| none_match(value, etag));
+    }
+    let since = request
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_http_date);
+    let last_modified = response
+        .get(header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_http_date);
+    matches!((since, last_modified), (Some(since), Some(modified)) if modified <= since)
+}
+
+/// Middleware implementing the conditional `GET` requests.
+///
+/// The `HEAD` requests are served as `GET` ones, then stripped of their body, so that
+/// they get the same validators.
+pub async fn conditional_get(mut request: Request, next: Next) -> Response {
+    let head = match *request.method() {
+        Method::GET => false,
+        Method::HEAD => true,
+        _ => return next.run(request).await,
+    };
+    let policy = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(DEFAULT_POLICY, |route| cache_policy(route.as_str()));
+    let request_headers = request.headers().clone();
+    *request.method_mut() = Method::GET;
+    let response = next.run(request).await;
+    let (mut parts, body) = response.into_parts();
+    if parts.status != StatusCode::OK {
+        return Response::from_parts(parts, strip(body, head));
+    }
+
+    parts
+        .headers
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static(policy));
+    if policy.contains("no-store") {
+        return Response::from_parts(parts, strip(body, head));
+    }
+
+    // The payload must be read entirely to compute its tag, unless it is streamed or
+    // too large.
+    let tagged = body
+        .size_hint()
+        .exact()
+        .is_some_and(|size| size <= MAX_TAGGED_SIZE);
+    let (body, etag) = if tagged {
+        let payload = match to_bytes(body, usize::MAX).await {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("cannot read the response payload: {e}");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        let etag = entity_tag(&payload);
+        parts.headers.insert(
+            header::ETAG,
+            HeaderValue::from_str(&etag).expect("an hexadecimal tag is a valid header value"),
+        );
+        parts
+            .headers
+            .insert(header::CONTENT_LENGTH, HeaderValue::from(payload.len()));
+        (Body::from(payload), Some(etag))
+    } else {
+        (body, None)
+    };
+    if not_modified(&request_headers, &parts.headers, etag.as_deref()) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, strip(body, head))
+}
+
+/// Removes the body of the responses to the `HEAD` requests.
+fn strip(body: Body, head: bool) -> Body {
+    if head {
+        Body::empty()
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, middleware, routing::get, Router};
+    use futures::stream;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/ratings/{rating_id}", get(|| async { "payload" }))
+            .route(
+                "/reports",
+                get(|| async {
+                    Body::from_stream(stream::iter([Ok::<_, axum::Error>(Bytes::from("a,b\n"))]))
+                }),
+            )
+            .layer(middleware::from_fn(conditional_get))
+    }
+
+    fn request(method: Method, uri: &str) -> Request {
+        axum::http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cache_policy() {
+        assert_eq!(cache_policy("/reports/{year}"), "public, max-age=86400");
+        assert_eq!(cache_policy("/v2/reports/{year}"), "public, max-age=86400");
+        assert_eq!(cache_policy("/cities/submissions"), "private, no-store");
+        assert_eq!(cache_policy("/ratings"), DEFAULT_POLICY);
+    }
+
+    #[test]
+    fn test_http_date() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 7, 9, 5, 1).unwrap();
+        assert_eq!(http_date(&date), "Thu, 07 Mar 2024 09:05:01 GMT");
+        assert_eq!(parse_http_date(&http_date(&date)), Some(date));
+    }
+
+    #[test]
+    fn test_none_match() {
+        let etag = entity_tag(b"payload");
+        assert!(etag.starts_with("W/\""));
+        assert!(none_match(&etag, Some(&etag)));
+        assert!(none_match(
+            &format!("\"other\", {}", etag.trim_start_matches("W/")),
+            Some(&etag)
+        ));
+        assert!(none_match("*", Some(&etag)));
+        assert!(!none_match("\"other\"", Some(&etag)));
+        assert!(!none_match(&etag, None));
+    }
+
+    #[test]
+    fn test_last_modified() {
+        let older = Utc.with_ymd_and_hms(2024, 3, 6, 9, 5, 1).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 3, 7, 9, 5, 1).unwrap();
+        assert_eq!(
+            last_modified([newer, older]),
+            Some((
+                header::LAST_MODIFIED,
+                String::from("Thu, 07 Mar 2024 09:05:01 GMT")
+            ))
+        );
+        assert_eq!(last_modified(Vec::<DateTime<Utc>>::new()), None);
+    }
+
+    #[test]
+    fn test_not_modified_since() {
+        let etag = entity_tag(b"payload");
+        let mut request = HeaderMap::new();
+        request.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Thu, 07 Mar 2024 09:05:01 GMT"),
+        );
+        let mut response = HeaderMap::new();
+        response.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 06 Mar 2024 09:05:01 GMT"),
+        );
+        assert!(not_modified(&request, &response, Some(&etag)));
+        assert!(not_modified(&request, &response, None));
+
+        response.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_static("Fri, 08 Mar 2024 09:05:01 GMT"),
+        );
+        assert!(!not_modified(&request, &response, Some(&etag)));
+
+        // The entity tag takes precedence over the date.
+        request.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&etag).unwrap());
+        assert!(not_modified(&request, &response, Some(&etag)));
+    }
+
+    #[tokio::test]
+    async fn test_head_has_the_get_validators() {
+        let get = app()
+            .oneshot(request(Method::GET, "/ratings/1"))
+            .await
+            .unwrap();
+        let head = app()
+            .oneshot(request(Method::HEAD, "/ratings/1"))
+            .await
+            .unwrap();
+        assert_eq!(get.headers()[header::ETAG], entity_tag(b"payload"));
+        assert_eq!(head.headers()[header::ETAG], get.headers()[header::ETAG]);
+        assert_eq!(head.headers()[header::CONTENT_LENGTH], "7");
+        let body = to_bytes(head.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_payload_is_not_buffered() {
+        let response = app()
+            .oneshot(request(Method::GET, "/reports"))
+            .await
+            .unwrap();
+        assert!(response.headers().get(header::ETAG).is_none());
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=86400"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "a,b\n");
+    }
+}
@@ -0,0 +1,434 @@
+//! Metrics of the API.
+//!
+//! The metrics are kept in memory and exposed in the Prometheus text format on
+//! `/metrics` in standalone mode. In Lambda mode, where no scraper can reach the
+//! function, they are emitted to CloudWatch as Embedded Metric Format (EMF) log lines
+//! instead.
+//!
+//! Requests are measured by the [`track_metrics`] middleware, labelled with the route
+//! being served. Database queries are measured by the [`observe_db_query`] callback,
+//! labelled with the adaptor issuing them, as set by [`adaptor`].
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::Instant;
+
+/// CloudWatch namespace of the metrics.
+pub const NAMESPACE: &str = "BNA/API";
+
+/// Number of requests served.
+pub const HTTP_REQUESTS: &str = "bna_api_http_requests_total";
+/// Duration of the requests, in seconds.
+pub const HTTP_REQUEST_DURATION: &str = "bna_api_http_request_duration_seconds";
+/// Duration of the database queries, in seconds.
+pub const DB_QUERY_DURATION: &str = "bna_api_db_query_duration_seconds";
+/// Number of submissions waiting for a review.
+pub const SUBMISSIONS_PENDING: &str = "bna_api_submissions_pending";
+/// Number of pipelines being processed.
+pub const PIPELINES_PROCESSING: &str = "bna_api_pipelines_processing";
+/// Number of lookups in the response cache.
+pub const CACHE_LOOKUPS: &str = "bna_api_cache_lookups_total";
+
+/// Upper bounds of the histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Global metrics registry.
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::default);
+
+/// Whether the metrics are emitted as EMF log lines.
+static EMF: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+    /// Metrics of the request being processed.
+    static CURRENT_REQUEST: Arc<RequestMetrics>;
+    /// Name of the adaptor being run.
+    static CURRENT_ADAPTOR: &'static str;
+}
+
+/// Returns the global metrics registry.
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+/// Emits the metrics as CloudWatch EMF log lines.
+pub fn enable_emf() {
+    EMF.store(true, Ordering::Relaxed);
+}
+
+/// Labels of a series, i.e. `[("route", "/cities")]`.
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Families {
+    counters: BTreeMap<(&'static str, Labels), u64>,
+    gauges: BTreeMap<(&'static str, Labels), f64>,
+    histograms: BTreeMap<(&'static str, Labels), Histogram>,
+}
+
+/// Collects the counters, gauges and histograms of the API.
+#[derive(Debug, Default)]
+pub struct Registry {
+    families: Mutex<Families>,
+}
+
+impl Registry {
+    /// Increments a counter.
+    pub fn increment(&self, name: &'static str, labels: Labels) {
+        *self.lock().counters.entry((name, labels)).or_default() += 1;
+    }
+
+    /// Sets the value of a gauge.
+    pub fn set(&self, name: &'static str, labels: Labels, value: f64) {
+        self.lock().gauges.insert((name, labels), value);
+    }
+
+    /// Records an observation in a histogram.
+    pub fn observe(&self, name: &'static str, labels: Labels, value: f64) {
+        self.lock()
+            .histograms
+            .entry((name, labels))
+            .or_default()
+            .observe(value);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let families = self.lock();
+        let mut out = String::new();
+        let mut previous = "";
+        for ((name, labels), value) in &families.counters {
+            header(&mut out, &mut previous, name, "counter");
+            let _ = writeln!(out, "{name}{} {value}", format_labels(labels, None));
+        }
+        for ((name, labels), value) in &families.gauges {
+            header(&mut out, &mut previous, name, "gauge");
+            let _ = writeln!(out, "{name}{} {value}", format_labels(labels, None));
+        }
+        for ((name, labels), histogram) in &families.histograms {
+            header(&mut out, &mut previous, name, "histogram");
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                let le = bound.to_string();
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{} {count}",
+                    format_labels(labels, Some(&le))
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_bucket{} {}",
+                format_labels(labels, Some("+Inf")),
+                histogram.count
+            );
+            let labels = format_labels(labels, None);
+            let _ = writeln!(out, "{name}_sum{labels} {}", histogram.sum);
+            let _ = writeln!(out, "{name}_count{labels} {}", histogram.count);
+        }
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Families> {
+        self.families.lock().expect("a healthy lock")
+    }
+}
+
+/// Writes the `TYPE` line of a metric family, once.
+fn header<'a>(out: &mut String, previous: &mut &'a str, name: &'a str, kind: &str) {
+    if *previous != name {
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        *previous = name;
+    }
+}
+
+/// Formats the labels of a series, i.e. `{route="/cities",le="0.5"}`.
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs = labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+        .collect::<Vec<_>>();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+/// Escapes a label value as required by the Prometheus text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics accumulated while processing a request.
+#[derive(Debug)]
+struct RequestMetrics {
+    db_queries: AtomicU64,
+    db_micros: AtomicU64,
+}
+
+/// Middleware measuring the requests.
+///
+/// The requests are labelled with their route template rather than their path, to keep
+/// the number of series bounded.
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let current = Arc::new(RequestMetrics {
+        db_queries: AtomicU64::new(0),
+        db_micros: AtomicU64::new(0),
+    });
+    let start = Instant::now();
+    let response = CURRENT_REQUEST
+        .scope(current.clone(), next.run(request))
+        .await;
+    let elapsed = start.elapsed();
+    let status_class = status_class(response.status().as_u16());
+
+    let registry = registry();
+    registry.increment(
+        HTTP_REQUESTS,
+        vec![
+            ("method", method.clone()),
+            ("route", route.clone()),
+            ("status_class", status_class.clone()),
+        ],
+    );
+    registry.observe(
+        HTTP_REQUEST_DURATION,
+        vec![("method", method.clone()), ("route", route.clone())],
+        elapsed.as_secs_f64(),
+    );
+    if EMF.load(Ordering::Relaxed) {
+        let db_duration = Duration::from_micros(current.db_micros.load(Ordering::Relaxed));
+        emit(emf_document(
+            &[&["method", "route"], &["status_class"]],
+            &[
+                ("Requests", "Count", 1.0),
+                ("Latency", "Milliseconds", millis(elapsed)),
+                (
+                    "DbQueries",
+                    "Count",
+                    current.db_queries.load(Ordering::Relaxed) as f64,
+                ),
+                ("DbDuration", "Milliseconds", millis(db_duration)),
+            ],
+            &[
+                ("method", method),
+                ("route", route),
+                ("status_class", status_class),
+            ],
+        ));
+    }
+    response
+}
+
+/// Runs an adaptor, labelling the database queries it issues with its name.
+pub async fn adaptor<F: Future>(name: &'static str, f: F) -> F::Output {
+    CURRENT_ADAPTOR.scope(name, f).await
+}
+
+/// Database metric callback measuring the queries.
+///
+/// The queries are labelled with the adaptor issuing them, or as `none` when issued
+/// outside of an adaptor. They are also added to the totals of the current request.
+pub fn observe_db_query(info: &sea_orm::metric::Info<'_>) {
+    let _ = CURRENT_REQUEST.try_with(|current| {
+        current.db_queries.fetch_add(1, Ordering::Relaxed);
+        current
+            .db_micros
+            .fetch_add(info.elapsed.as_micros() as u64, Ordering::Relaxed);
+    });
+    let adaptor = CURRENT_ADAPTOR.try_with(|name| *name).unwrap_or("none");
+    registry().observe(
+        DB_QUERY_DURATION,
+        vec![
+            ("adaptor", adaptor.to_string()),
+            ("failed", info.failed.to_string()),
+        ],
+        info.elapsed.as_secs_f64(),
+    );
+}
+
+/// Sets a domain gauge, e.g. the number of pending submissions.
+pub fn set_gauge(name: &'static str, value: f64) {
+    registry().set(name, Vec::new(), value);
+    if EMF.load(Ordering::Relaxed) {
+        emit(emf_document(&[&[]], &[(name, "Count", value)], &[]));
+    }
+}
+
+/// Counts a lookup in the response cache, labelled with the cached query.
+pub fn observe_cache_lookup(cache: &'static str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    registry().increment(
+        CACHE_LOOKUPS,
+        vec![("cache", cache.to_string()), ("result", result.to_string())],
+    );
+    if EMF.load(Ordering::Relaxed) {
+        let metric = if hit { "CacheHits" } else { "CacheMisses" };
+        emit(emf_document(
+            &[&["cache"]],
+            &[(metric, "Count", 1.0)],
+            &[("cache", cache.to_string())],
+        ));
+    }
+}
+
+/// Returns the class of a status code, i.e. `2xx`.
+fn status_class(status: u16) -> String {
+    format!("{}xx", status / 100)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Builds an EMF document.
+///
+/// See <https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch_Embedded_Metric_Format_Specification.html>.
+fn emf_document(
+    dimensions: &[&[&str]],
+    metrics: &[(&str, &str, f64)],
+    properties: &[(&str, String)],
+) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let mut document = Map::new();
+    document.insert(
+        "_aws".to_string(),
+        json!({
+            "Timestamp": timestamp,
+            "CloudWatchMetrics": [{
+                "Namespace": NAMESPACE,
+                "Dimensions": dimensions,
+                "Metrics": metrics
+                    .iter()
+                    .map(|(name, unit, _)| json!({"Name": name, "Unit": unit}))
+                    .collect::<Vec<_>>(),
+            }],
+        }),
+    );
+    for (name, _, value) in metrics {
+        document.insert(name.to_string(), json!(value));
+    }
+    for (name, value) in properties {
+        document.insert(name.to_string(), json!(value));
+    }
+    Value::Object(document)
+}
+
+/// Writes an EMF document to the standard output, collected by CloudWatch Logs.
+fn emit(document: Value) {
+    println!("{document}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let registry = Registry::default();
+        registry.increment(HTTP_REQUESTS, vec![("route", "/cities".into())]);
+        registry.increment(HTTP_REQUESTS, vec![("route", "/cities".into())]);
+        registry.set(SUBMISSIONS_PENDING, Vec::new(), 3.0);
+        registry.observe(
+            DB_QUERY_DURATION,
+            vec![("adaptor", "get_cities_adaptor".into())],
+            0.2,
+        );
+        let rendered = registry.render();
+        assert!(rendered.contains("# TYPE bna_api_http_requests_total counter\n"));
+        assert!(rendered.contains("bna_api_http_requests_total{route=\"/cities\"} 2\n"));
+        assert!(rendered.contains("bna_api_submissions_pending 3\n"));
+        assert!(rendered.contains(
+            "bna_api_db_query_duration_seconds_bucket{adaptor=\"get_cities_adaptor\",le=\"0.1\"} 0\n"
+        ));
+        assert!(rendered.contains(
+            "bna_api_db_query_duration_seconds_bucket{adaptor=\"get_cities_adaptor\",le=\"0.25\"} 1\n"
+        ));
+        assert!(rendered.contains(
+            "bna_api_db_query_duration_seconds_count{adaptor=\"get_cities_adaptor\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_emf_document() {
+        let document = emf_document(
+            &[&["route"]],
+            &[("Requests", "Count", 1.0)],
+            &[("route", "/cities".to_string())],
+        );
+        assert_eq!(
+            document["_aws"]["CloudWatchMetrics"][0]["Namespace"],
+            NAMESPACE
+        );
+        assert_eq!(
+            document["_aws"]["CloudWatchMetrics"][0]["Dimensions"],
+            json!([["route"]])
+        );
+        assert_eq!(document["Requests"], 1.0);
+        assert_eq!(document["route"], "/cities");
+    }
+
+    #[tokio::test]
+    async fn test_adaptor() {
+        let name = adaptor("get_cities_adaptor", async {
+            CURRENT_ADAPTOR.try_with(|name| *name).ok()
+        })
+        .await;
+        assert_eq!(name, Some("get_cities_adaptor"));
+        assert!(CURRENT_ADAPTOR.try_with(|name| *name).is_err());
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+}
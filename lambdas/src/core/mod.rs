@@ -1,2 +1,15 @@
+pub mod config;
+pub mod cors;
+pub mod extract;
+pub mod http_cache;
 pub mod link_header;
+pub mod metrics;
+pub mod openapi;
+pub mod queue;
 pub mod resource;
+pub mod response_cache;
+pub mod server;
+pub mod telemetry;
+pub mod validation;
+pub mod versioning;
+pub mod webhook;
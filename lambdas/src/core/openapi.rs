@@ -0,0 +1,611 @@
+//! OpenAPI specifications of the API.
+//!
+//! Each version of the API is described by an OpenAPI 3.1 document, generated from its
+//! routes, and by the down-conversion of this document to OpenAPI 3.0, from which the
+//! `bnaclient` crate is generated. Both are committed at the root of the repository:
+//!
+//! | API version | OpenAPI 3.1           | OpenAPI 3.0           |
+//! | ----------- | --------------------- | --------------------- |
+//! | v1          | `openapi-3.1.yaml`    | `openapi-3.0.yaml`    |
+//! | v2          | `openapi-v2-3.1.yaml` | `openapi-v2-3.0.yaml` |
+//!
+//! The files are written by the `openapi-spec` binary, which also checks that they are
+//! up to date with the routes when run with `--check`.
+use crate::core::{
+    resource::{
+        schema::{
+            document_problem_details, APIError, APIErrorSource, APIErrors, OrderDirection,
+            ProblemDetails, ProblemFieldError,
+        },
+        system,
+    },
+    versioning::{self, ApiVersion},
+};
+use axum::{middleware, Router};
+use serde_norway::{Mapping, Value};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use utoipa::{
+    openapi::{Components, ContactBuilder, Info, OpenApi, OpenApiBuilder, Server, Tag},
+    schema,
+};
+use utoipa_axum::router::OpenApiRouter;
+
+/// Keys of the maps whose entries are named by the users, i.e. the properties of a
+/// schema, and not by the OpenAPI specification.
+const NAMED_MAPS: &[&str] = &[
+    "callbacks",
+    "content",
+    "encoding",
+    "examples",
+    "headers",
+    "links",
+    "mapping",
+    "parameters",
+    "paths",
+    "patternProperties",
+    "properties",
+    "requestBodies",
+    "responses",
+    "schemas",
+    "securitySchemes",
+    "variables",
+];
+
+/// Keys of the literal values, i.e. the examples, which are never converted.
+const LITERALS: &[&str] = &["const", "default", "enum", "example", "value"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenApiError {
+    /// The specification cannot be serialized or a specification file cannot be parsed.
+    #[error("invalid specification: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    /// A specification file cannot be read.
+    #[error("cannot read {0}: {1}")]
+    Read(PathBuf, io::Error),
+
+    /// A specification file cannot be written.
+    #[error("cannot write {0}: {1}")]
+    Write(PathBuf, io::Error),
+
+    /// A specification file differs from the one generated from the routes.
+    #[error("{0} is out of date, starting at `{1}`, regenerate it with `just generate-oas`")]
+    Drift(PathBuf, String),
+}
+
+/// Version of the OpenAPI specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OasVersion {
+    V3_0,
+    V3_1,
+}
+
+impl fmt::Display for OasVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OasVersion::V3_0 => write!(f, "3.0"),
+            OasVersion::V3_1 => write!(f, "3.1"),
+        }
+    }
+}
+
+/// Returns the name of the file describing a version of the API.
+pub fn specification_file(version: ApiVersion, oas: OasVersion) -> String {
+    match version {
+        ApiVersion::V1 => format!("openapi-{oas}.yaml"),
+        ApiVersion::V2 => format!("openapi-v2-{oas}.yaml"),
+    }
+}
+
+/// Builds the routes of the API, without their middlewares, along with the
+/// specification of each version.
+///
+/// Each version is mounted under its prefix. The system routes are not versioned and
+/// the `/v1` routes are still served without prefix, for the clients written before
+/// the versioning.
+pub fn routes() -> (Router, Vec<(ApiVersion, OpenApi)>) {
+    let (system, system_api) = system::endpoint::routes().split_for_parts();
+    let (v1, mut api_v1) = OpenApiRouter::with_openapi(openapi(ApiVersion::V1))
+        .nest(
+            ApiVersion::V1.prefix(),
+            ApiVersion::V1
+                .routes()
+                .layer(middleware::from_fn(versioning::track_v1_lifecycle)),
+        )
+        .split_for_parts();
+    let (v2, mut api_v2) = OpenApiRouter::with_openapi(openapi(ApiVersion::V2))
+        .nest(ApiVersion::V2.prefix(), ApiVersion::V2.routes())
+        .split_for_parts();
+    for api in [&mut api_v1, &mut api_v2] {
+        api.merge(system_api.clone());
+        document_problem_details(api);
+    }
+
+    let (unversioned, _) = ApiVersion::V1
+        .routes()
+        .layer(middleware::from_fn(versioning::deprecate_unversioned))
+        .split_for_parts();
+
+    let router = Router::new()
+        .merge(v1)
+        .merge(v2)
+        .merge(unversioned)
+        .merge(system);
+    (
+        router,
+        vec![(ApiVersion::V1, api_v1), (ApiVersion::V2, api_v2)],
+    )
+}
+
+/// Renders the specification files of the API, by file name.
+pub fn specification_files(
+    specifications: &[(ApiVersion, OpenApi)],
+) -> Result<Vec<(String, String)>, OpenApiError> {
+    let mut files = Vec::with_capacity(specifications.len() * 2);
+    for (version, api) in specifications {
+        files.push((
+            specification_file(*version, OasVersion::V3_1),
+            api.to_yaml()?,
+        ));
+        files.push((
+            specification_file(*version, OasVersion::V3_0),
+            serde_norway::to_string(&down_convert(serde_norway::to_value(api)?))?,
+        ));
+    }
+    Ok(files)
+}
+
+/// Writes the specification files of the API into a directory.
+pub fn write_specifications(
+    dir: &Path,
+    specifications: &[(ApiVersion, OpenApi)],
+) -> Result<Vec<PathBuf>, OpenApiError> {
+    specification_files(specifications)?
+        .into_iter()
+        .map(|(name, content)| {
+            let path = dir.join(name);
+            fs::write(&path, content).map_err(|e| OpenApiError::Write(path.clone(), e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Checks that the specification files of a directory describe the API.
+///
+/// The documents are compared, not their formatting.
+pub fn check_specifications(
+    dir: &Path,
+    specifications: &[(ApiVersion, OpenApi)],
+) -> Result<(), OpenApiError> {
+    for (name, content) in specification_files(specifications)? {
+        let path = dir.join(name);
+        let committed =
+            fs::read_to_string(&path).map_err(|e| OpenApiError::Read(path.clone(), e))?;
+        let committed: Value = serde_norway::from_str(&committed)?;
+        let generated: Value = serde_norway::from_str(&content)?;
+        if let Some(pointer) = first_difference(&generated, &committed, String::new()) {
+            return Err(OpenApiError::Drift(path, pointer));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the JSON pointer of the first difference between two documents.
+fn first_difference(generated: &Value, committed: &Value, pointer: String) -> Option<String> {
+    match (generated, committed) {
+        (Value::Mapping(generated), Value::Mapping(committed)) => generated
+            .iter()
+            .find_map(|(key, value)| {
+                let pointer = format!("{pointer}/{}", pointer_token(key));
+                match committed.get(key) {
+                    Some(other) => first_difference(value, other, pointer),
+                    None => Some(pointer),
+                }
+            })
+            .or_else(|| {
+                committed
+                    .keys()
+                    .find(|key| !generated.contains_key(key))
+                    .map(|key| format!("{pointer}/{}", pointer_token(key)))
+            }),
+        (Value::Sequence(generated), Value::Sequence(committed)) => generated
+            .iter()
+            .zip(committed)
+            .enumerate()
+            .find_map(|(i, (value, other))| {
+                first_difference(value, other, format!("{pointer}/{i}"))
+            })
+            .or_else(|| {
+                (generated.len() != committed.len())
+                    .then(|| format!("{pointer}/{}", generated.len().min(committed.len())))
+            }),
+        (generated, committed) => (generated != committed).then(|| {
+            if pointer.is_empty() {
+                "/".to_string()
+            } else {
+                pointer
+            }
+        }),
+    }
+}
+
+/// Escapes a mapping key as a JSON pointer token.
+fn pointer_token(key: &Value) -> String {
+    let key = match key {
+        Value::String(key) => key.clone(),
+        key => serde_norway::to_string(key)
+            .map(|key| key.trim_end().to_string())
+            .unwrap_or_default(),
+    };
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Down-converts an OpenAPI 3.1 document to OpenAPI 3.0.
+///
+/// The 3.1 constructs emitted for the API are rewritten with their 3.0 equivalents:
+/// - the `null` type of the optional values becomes `nullable`,
+/// - the `examples` of the schemas become a single `example`,
+/// - `const` becomes a single valued `enum`,
+/// - the numeric `exclusiveMinimum` and `exclusiveMaximum` become boolean flags of
+///   `minimum` and `maximum`,
+/// - `contentMediaType` and `contentEncoding` become the `binary` and `byte` formats.
+///
+/// The keywords unknown to OpenAPI 3.0 are dropped.
+pub fn down_convert(document: Value) -> Value {
+    let Value::Mapping(mut document) = document else {
+        return document;
+    };
+    document.insert("openapi".into(), "3.0.3".into());
+    document.shift_remove("jsonSchemaDialect");
+    document.shift_remove("webhooks");
+    if let Some(info) = document.get_mut("info").and_then(Value::as_mapping_mut) {
+        info.shift_remove("summary");
+        if let Some(license) = info.get_mut("license").and_then(Value::as_mapping_mut) {
+            license.shift_remove("identifier");
+        }
+    }
+    convert(Value::Mapping(document))
+}
+
+/// Converts a value of the document and its children.
+fn convert(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(convert_mapping(mapping)),
+        Value::Sequence(sequence) => Value::Sequence(sequence.into_iter().map(convert).collect()),
+        value => value,
+    }
+}
+
+/// Converts the keywords of a mapping, keeping their order.
+fn convert_mapping(mapping: Mapping) -> Mapping {
+    let mut converted = Mapping::new();
+    for (key, value) in mapping {
+        let Some(name) = key.as_str().map(str::to_owned) else {
+            converted.insert(key, convert(value));
+            continue;
+        };
+        match (name.as_str(), value) {
+            ("type", Value::Sequence(types)) => {
+                let (nulls, mut types): (Vec<_>, Vec<_>) =
+                    types.into_iter().partition(|t| t.as_str() == Some("null"));
+                match types.len() {
+                    0 => {}
+                    1 => {
+                        converted.insert(key, types.remove(0));
+                    }
+                    _ => {
+                        let schemas = types.into_iter().map(|t| {
+                            let mut schema = Mapping::new();
+                            schema.insert("type".into(), t);
+                            Value::Mapping(schema)
+                        });
+                        converted.insert("oneOf".into(), Value::Sequence(schemas.collect()));
+                    }
+                }
+                if !nulls.is_empty() {
+                    converted.insert("nullable".into(), true.into());
+                }
+            }
+            ("oneOf" | "anyOf", Value::Sequence(schemas)) if schemas.iter().any(is_null) => {
+                let schemas: Vec<_> = schemas
+                    .into_iter()
+                    .filter(|schema| !is_null(schema))
+                    .map(convert)
+                    .collect();
+                match schemas.len() {
+                    0 => {}
+                    1 => {
+                        converted.insert("allOf".into(), Value::Sequence(schemas));
+                    }
+                    _ => {
+                        converted.insert(key, Value::Sequence(schemas));
+                    }
+                }
+                converted.insert("nullable".into(), true.into());
+            }
+            ("examples", Value::Sequence(examples)) => {
+                if let Some(example) = examples.into_iter().next() {
+                    converted.insert("example".into(), example);
+                }
+            }
+            ("const", value) => {
+                converted.insert("enum".into(), Value::Sequence(vec![value]));
+            }
+            ("exclusiveMinimum", Value::Number(bound)) => {
+                converted.insert("minimum".into(), Value::Number(bound));
+                converted.insert(key, true.into());
+            }
+            ("exclusiveMaximum", Value::Number(bound)) => {
+                converted.insert("maximum".into(), Value::Number(bound));
+                converted.insert(key, true.into());
+            }
+            ("contentMediaType", _) => {
+                if !converted.contains_key("format") {
+                    converted.insert("format".into(), "binary".into());
+                }
+            }
+            ("contentEncoding", encoding) => {
+                if encoding.as_str() == Some("base64") {
+                    converted.insert("format".into(), "byte".into());
+                }
+            }
+            ("$schema" | "$id" | "$comment" | "unevaluatedProperties", _) => {}
+            (name, value) if LITERALS.contains(&name) => {
+                converted.insert(key, value);
+            }
+            (name, Value::Mapping(entries)) if NAMED_MAPS.contains(&name) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(name, entry)| (name, convert(entry)))
+                    .collect();
+                converted.insert(key, Value::Mapping(entries));
+            }
+            (_, value) => {
+                converted.insert(key, convert(value));
+            }
+        }
+    }
+    converted
+}
+
+/// Whether a schema only allows `null`.
+fn is_null(schema: &Value) -> bool {
+    schema
+        .as_mapping()
+        .and_then(|schema| schema.get("type"))
+        .and_then(Value::as_str)
+        == Some("null")
+}
+
+/// Defines the OpenAPI specification of a version of the API.
+pub fn openapi(version: ApiVersion) -> OpenApi {
+    OpenApiBuilder::new()
+        .info(
+            Info::builder()
+                .title("BNA REST API")
+                .version(version.document_version())
+                .description(Some(
+                    "Provides a way to retrieve the BNA results programmatically.",
+                ))
+                .contact(Some(
+                    ContactBuilder::new()
+                        .name(Some("The BNA Mechanics team"))
+                        .url(Some("https://peopleforbikes.github.io/"))
+                        .build(),
+                ))
+                .build(),
+        )
+        .servers(Some(vec![
+            Server::builder()
+                .description(Some("Local development API"))
+                .url("http://localhost:3000")
+                .build(),
+            Server::builder()
+                .description(Some("Staging API"))
+                .url("https://api.staging.bna.peopleforbikes.org")
+                .build(),
+            Server::builder()
+                .description(Some("Production API"))
+                .url("https://api.bna.peopleforbikes.org")
+                .build(),
+        ]))
+        .tags(Some(vec![
+            Tag::builder()
+                .name("city")
+                .description(Some("City API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("pipeline")
+                .description(Some("Pipeline API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("price")
+                .description(Some("Price API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("rating")
+                .description(Some("Rating API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("reports")
+                .description(Some("Reports API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("usstate")
+                .description(Some("US State API endpoints"))
+                .build(),
+            Tag::builder()
+                .name("webhook")
+                .description(Some("Webhook API endpoints"))
+                .build(),
+        ]))
+        .components(Some(
+            Components::builder()
+                .schema(
+                    "APIError",
+                    schema!(
+                        #[inline]
+                        APIError
+                    ),
+                )
+                .schema(
+                    "APIErrorSource",
+                    schema!(
+                        #[inline]
+                        APIErrorSource
+                    ),
+                )
+                .schema(
+                    "APIErrors",
+                    schema!(
+                        #[inline]
+                        APIErrors
+                    ),
+                )
+                .schema(
+                    "ProblemDetails",
+                    schema!(
+                        #[inline]
+                        ProblemDetails
+                    ),
+                )
+                .schema(
+                    "ProblemFieldError",
+                    schema!(
+                        #[inline]
+                        ProblemFieldError
+                    ),
+                )
+                .schema(
+                    "OrderDirection",
+                    schema!(
+                        #[inline]
+                        OrderDirection
+                    ),
+                )
+                .build(),
+        ))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(document: &str) -> Value {
+        serde_norway::from_str(document).unwrap()
+    }
+
+    #[test]
+    fn test_down_convert() {
+        let document = yaml(
+            r#"
+openapi: 3.1.0
+info:
+  title: BNA REST API
+  license:
+    name: MIT
+    identifier: MIT
+paths: {}
+components:
+  schemas:
+    Rating:
+      type: object
+      properties:
+        type:
+          type:
+          - string
+          - 'null'
+          examples:
+          - city
+        score:
+          type: number
+          exclusiveMinimum: 0
+        state:
+          oneOf:
+          - type: 'null'
+          - $ref: '#/components/schemas/State'
+        version:
+          const: v1
+"#,
+        );
+        let expected = yaml(
+            r#"
+openapi: 3.0.3
+info:
+  title: BNA REST API
+  license:
+    name: MIT
+paths: {}
+components:
+  schemas:
+    Rating:
+      type: object
+      properties:
+        type:
+          type: string
+          nullable: true
+          example: city
+        score:
+          type: number
+          minimum: 0
+          exclusiveMinimum: true
+        state:
+          allOf:
+          - $ref: '#/components/schemas/State'
+          nullable: true
+        version:
+          enum:
+          - v1
+"#,
+        );
+        assert_eq!(down_convert(document), expected);
+    }
+
+    #[test]
+    fn test_down_convert_keeps_the_examples() {
+        let document = yaml(
+            r#"
+content:
+  application/json:
+    example:
+      type:
+      - string
+      - 'null'
+    examples:
+      first:
+        value:
+          const: v1
+"#,
+        );
+        assert_eq!(convert(document.clone()), document);
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let generated = yaml("paths:\n  /v1/ratings:\n    get:\n      tags: [rating]\n");
+        let committed = yaml("paths:\n  /v1/ratings:\n    get:\n      tags: [city]\n");
+        assert_eq!(
+            first_difference(&generated, &committed, String::new()),
+            Some("/paths/~1v1~1ratings/get/tags/0".to_string())
+        );
+        assert_eq!(
+            first_difference(&generated, &generated, String::new()),
+            None
+        );
+    }
+
+    /// Fails when the committed specifications no longer describe the routes, in which
+    /// case they, and the `bnaclient`, must be regenerated.
+    #[test]
+    fn test_specifications_are_up_to_date() {
+        let (_, specifications) = routes();
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        if let Err(e) = check_specifications(&root, &specifications) {
+            panic!("{e}");
+        }
+    }
+}
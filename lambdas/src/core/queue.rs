@@ -0,0 +1,256 @@
+//! Queues used to send the analysis requests to the Brokenspoke analyzer.
+//!
+//! The SQS queue is used when `queue.analysis_url` is configured, i.e. with the
+//! `BNA_API_ANALYSIS_QUEUE_URL` environment variable. Otherwise the messages are kept
+//! in memory, which is convenient for local runs and tests.
+use crate::core::config::{config, QueueConfig};
+use entity::{city, submission};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::{future::Future, sync::Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Global static analysis queue.
+static ANALYSIS_QUEUE: OnceCell<AnalysisQueue> = OnceCell::new();
+
+/// Message requesting the analysis of a city.
+///
+/// This is also the shape of the `sqs_message` stored with a BNA pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisMessage {
+    /// Identifier of the BNA pipeline tracking the analysis.
+    pub pipeline_id: Uuid,
+    pub country: String,
+    pub city: String,
+    pub region: String,
+    pub fips_code: String,
+    /// Version of the analyzer to use, the latest one if not specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl AnalysisMessage {
+    /// Creates the message requesting the analysis of a submitted city.
+    ///
+    /// Returns `None` if the submission does not specify the region of the city.
+    pub fn from_submission(pipeline_id: Uuid, value: &submission::Model) -> Option<Self> {
+        Some(Self {
+            pipeline_id,
+            country: value.country.clone(),
+            city: value.city.clone(),
+            region: value.region.clone()?,
+            fips_code: value.fips_code.clone(),
+            version: None,
+        })
+    }
+
+    /// Creates the message requesting the analysis of a city.
+    ///
+    /// Returns `None` if the city has no FIPS code, "0" being the one of the cities
+    /// outside of the United States.
+    pub fn from_city(pipeline_id: Uuid, value: &city::Model) -> Option<Self> {
+        Some(Self {
+            pipeline_id,
+            country: value.country.clone(),
+            city: value.name.clone(),
+            region: value.state.clone(),
+            fips_code: value.fips_code.clone().filter(|f| !f.trim().is_empty())?,
+            version: None,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    /// The message cannot be serialized.
+    #[error("cannot serialize the message: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    /// The message cannot be sent.
+    #[error("cannot send the message: {0}")]
+    Send(String),
+}
+
+/// Defines a queue accepting analysis messages.
+pub trait Queue: Send + Sync {
+    /// Sends a message and returns its identifier.
+    fn send(
+        &self,
+        message: &AnalysisMessage,
+    ) -> impl Future<Output = Result<String, QueueError>> + Send;
+}
+
+/// Queue backed by Amazon SQS.
+#[derive(Debug, Clone)]
+pub struct SqsQueue {
+    client: aws_sdk_sqs::Client,
+    queue_url: String,
+}
+
+impl SqsQueue {
+    pub fn new(client: aws_sdk_sqs::Client, queue_url: &str) -> Self {
+        Self {
+            client,
+            queue_url: queue_url.to_string(),
+        }
+    }
+
+    /// Creates a queue using the default AWS configuration.
+    pub async fn from_url(queue_url: &str) -> Self {
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self::new(aws_sdk_sqs::Client::new(&config), queue_url)
+    }
+
+    /// Checks that the queue can be reached.
+    pub async fn check(&self) -> Result<(), QueueError> {
+        self.client
+            .get_queue_attributes()
+            .queue_url(&self.queue_url)
+            .attribute_names(aws_sdk_sqs::types::QueueAttributeName::ApproximateNumberOfMessages)
+            .send()
+            .await
+            .map_err(|e| {
+                QueueError::Send(aws_sdk_sqs::error::DisplayErrorContext(e).to_string())
+            })?;
+        Ok(())
+    }
+}
+
+impl Queue for SqsQueue {
+    async fn send(&self, message: &AnalysisMessage) -> Result<String, QueueError> {
+        let body = serde_json::to_string(message)?;
+        let output = self
+            .client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                QueueError::Send(aws_sdk_sqs::error::DisplayErrorContext(e).to_string())
+            })?;
+        Ok(output.message_id().unwrap_or_default().to_string())
+    }
+}
+
+/// Queue keeping the messages in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryQueue {
+    messages: Mutex<Vec<AnalysisMessage>>,
+}
+
+impl InMemoryQueue {
+    /// Returns a copy of the messages sent so far.
+    pub fn messages(&self) -> Vec<AnalysisMessage> {
+        self.messages.lock().expect("a healthy lock").clone()
+    }
+}
+
+impl Queue for InMemoryQueue {
+    async fn send(&self, message: &AnalysisMessage) -> Result<String, QueueError> {
+        info!("queuing analysis message in memory: {:?}", message);
+        self.messages
+            .lock()
+            .expect("a healthy lock")
+            .push(message.clone());
+        Ok(Uuid::new_v4().to_string())
+    }
+}
+
+/// Queue selected by the configuration.
+#[derive(Debug)]
+pub enum AnalysisQueue {
+    Sqs(SqsQueue),
+    InMemory(InMemoryQueue),
+}
+
+impl AnalysisQueue {
+    /// Creates the queue from the configuration.
+    pub async fn from_config(config: &QueueConfig) -> Self {
+        match &config.analysis_url {
+            Some(url) => Self::Sqs(SqsQueue::from_url(url).await),
+            None => {
+                warn!("queue.analysis_url is not set, analysis messages are kept in memory");
+                Self::InMemory(InMemoryQueue::default())
+            }
+        }
+    }
+
+    /// Checks that the queue can be reached.
+    ///
+    /// The in-memory queue is always reachable.
+    pub async fn check(&self) -> Result<(), QueueError> {
+        match self {
+            AnalysisQueue::Sqs(queue) => queue.check().await,
+            AnalysisQueue::InMemory(_) => Ok(()),
+        }
+    }
+}
+
+impl Queue for AnalysisQueue {
+    async fn send(&self, message: &AnalysisMessage) -> Result<String, QueueError> {
+        match self {
+            AnalysisQueue::Sqs(queue) => queue.send(message).await,
+            AnalysisQueue::InMemory(queue) => queue.send(message).await,
+        }
+    }
+}
+
+/// Returns the analysis queue, creating it on first use.
+pub(crate) async fn analysis_queue_or_init() -> &'static AnalysisQueue {
+    if let Some(queue) = ANALYSIS_QUEUE.get() {
+        queue
+    } else {
+        let queue = AnalysisQueue::from_config(&config().queue).await;
+        ANALYSIS_QUEUE.set(queue).ok();
+        ANALYSIS_QUEUE.get().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn submission(region: Option<&str>) -> submission::Model {
+        submission::Model {
+            id: 1,
+            first_name: "John".to_string(),
+            last_name: "Doe".to_string(),
+            occupation: None,
+            organization: None,
+            email: "john.doe@acme.org".to_string(),
+            country: "United States".to_string(),
+            city: "santa rosa".to_string(),
+            region: region.map(str::to_string),
+            fips_code: "3570670".to_string(),
+            consent: true,
+            status: "Approved".to_string(),
+            created_at: Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_analysis_message_from_submission() {
+        let pipeline_id = Uuid::parse_str("3fa85f64-5717-4562-b3fc-2c963f66afa6").unwrap();
+        let message =
+            AnalysisMessage::from_submission(pipeline_id, &submission(Some("new mexico"))).unwrap();
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({"pipeline_id":"3fa85f64-5717-4562-b3fc-2c963f66afa6","country":"United States","city":"santa rosa","region":"new mexico","fips_code":"3570670"})
+        );
+
+        assert!(AnalysisMessage::from_submission(pipeline_id, &submission(None)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_queue() {
+        let queue = InMemoryQueue::default();
+        let message =
+            AnalysisMessage::from_submission(Uuid::new_v4(), &submission(Some("new mexico")))
+                .unwrap();
+        queue.send(&message).await.unwrap();
+        assert_eq!(queue.messages(), vec![message]);
+    }
+}
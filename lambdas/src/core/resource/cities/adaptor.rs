@@ -1,20 +1,49 @@
-use super::db::{
-    fetch_cities, fetch_cities_ratings, fetch_cities_submission, fetch_cities_submissions,
-    fetch_cities_with_latest_summary, fetch_city, fetch_country, fetch_state_region_crosswalk,
-    fetch_top_cities,
+use super::{
+    db::{
+        count_cities_submissions_by_status, delete_cities_submission,
+        erase_cities_submissions_by_email, fetch_cities, fetch_cities_ratings,
+        fetch_cities_submission, fetch_cities_submission_for_update,
+        fetch_cities_submission_reviews, fetch_cities_submissions,
+        fetch_cities_submissions_by_email, fetch_cities_with_latest_summary, fetch_city,
+        fetch_country, fetch_matching_city, fetch_matching_open_submission,
+        fetch_state_region_crosswalk, fetch_top_cities, fetch_us_state_by_name,
+        lock_submission_location,
+    },
+    SubmissionFilter,
+};
+use crate::{
+    core::{
+        queue::{AnalysisMessage, Queue, QueueError},
+        resource::schema::OrderDirection,
+        response_cache::{cached, invalidate},
+        validation::{
+            is_email, is_fips_place_code, is_latitude, is_longitude, is_present, Validator,
+        },
+        webhook::record_event,
+    },
+    Context, ExecutionError,
 };
-use crate::{core::resource::schema::OrderDirection, Context, ExecutionError};
 use entity::{
-    city, summary,
+    bna_pipeline, city, country, submission, submission_review, summary, us_state,
     wrappers::{
+        bna_pipeline::BNAPipelinePost,
         city::CityPost,
-        submission::{SubmissionPatch, SubmissionPost},
+        submission::{SubmissionPatch, SubmissionPost, SubmissionReviewPost},
+        ApprovalStatus, WebhookEvent,
     },
 };
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, IntoActiveModel};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
+    DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, TransactionTrait,
+};
+use serde_json::json;
+use std::str::FromStr;
 use tracing::info;
 use uuid::Uuid;
 
+/// Placeholder replacing the erased personal data.
+const ERASED: &str = "[erased]";
+
 pub async fn get_city_adaptor(
     db: &DatabaseConnection,
     country: &str,
@@ -78,12 +107,12 @@ pub async fn get_cities_ratings_adaptor(
 
 pub async fn post_cities_adaptor(
     db: &DatabaseConnection,
-    city: CityPost,
+    mut city: CityPost,
+    ctx: Context,
 ) -> Result<city::Model, ExecutionError> {
-    // Ensure the country is a valid one.
-    if fetch_country(db, &city.country).await?.is_none() {
-        return Err(ExecutionError::UncoveredCountry(city.country));
-    }
+    // Validate the payload, and use the canonical names of the country and the US
+    // states.
+    validate_city(db, &mut city, &ctx).await?;
 
     // extract some fields from the post model.
     let country = city.country.clone();
@@ -97,12 +126,18 @@ pub async fn post_cities_adaptor(
     active_model.id = ActiveValue::Set(Uuid::new_v4());
 
     // If the country is the United States, set the BNA region.
-    if country.to_lowercase().eq("united states") {
+    if is_united_states(&country) {
         match fetch_state_region_crosswalk(db, &state).await? {
             Some(model) => {
                 active_model.region = ActiveValue::Set(Some(model.region));
             }
-            None => return Err(ExecutionError::InvalidUSState(state)),
+            None => {
+                return Err(ExecutionError::InvalidUSState(
+                    ctx.request_id(),
+                    ctx.source(),
+                    state,
+                ))
+            }
         }
     }
 
@@ -113,6 +148,7 @@ pub async fn post_cities_adaptor(
 
     // And insert a new entry.
     let model = active_model.insert(db).await?;
+    invalidate();
     Ok(model)
 }
 
@@ -120,13 +156,16 @@ pub async fn get_cities_submission_adaptor(
     db: &DatabaseConnection,
     submission_id: i32,
     status: Option<String>,
-    ctx: Context,
-) -> Result<entity::submission::Model, ExecutionError> {
+    ctx: &Context,
+) -> Result<(submission::Model, Vec<submission_review::Model>), ExecutionError> {
     // Fetch the model.
     let status_str = status.clone().unwrap_or("any".to_string());
     let model = fetch_cities_submission(db, submission_id, status).await?;
     match model {
-        Some(model) => Ok(model),
+        Some(model) => {
+            let reviews = fetch_cities_submission_reviews(db, submission_id).await?;
+            Ok((model, reviews))
+        }
         None => Err(ExecutionError::NotFound(
             ctx.request_id(),
             ctx.source(),
@@ -137,37 +176,371 @@ pub async fn get_cities_submission_adaptor(
 
 pub async fn get_cities_submissions_adaptor(
     db: &DatabaseConnection,
-    status: Option<String>,
+    filter: SubmissionFilter,
+    sort_direction: OrderDirection,
+    sort_by: &str,
     page: u64,
     page_size: u64,
-) -> Result<(u64, Vec<entity::submission::Model>), ExecutionError> {
-    // Fetch the model.
-    Ok(fetch_cities_submissions(db, status, page, page_size).await?)
+    ctx: &Context,
+) -> Result<(u64, Vec<submission::Model>, Vec<(String, i64)>), ExecutionError> {
+    // Searching the submissions by email would reveal the personal data.
+    if filter.email.is_some() && !ctx.is_admin() {
+        return Err(ExecutionError::Forbidden(
+            ctx.request_id(),
+            ctx.source(),
+            "only the administrators can filter the submissions by email".to_string(),
+        ));
+    }
+    let filter = SubmissionFilter {
+        personal_data: ctx.is_admin(),
+        ..filter
+    };
+
+    // Fetch the models and the number of submissions per status.
+    let (total, models) =
+        fetch_cities_submissions(db, &filter, sort_direction, sort_by, page, page_size).await?;
+    let status_counts = count_cities_submissions_by_status(db, &filter).await?;
+    Ok((total, models, status_counts))
 }
 
 pub async fn patch_cities_submission_adaptor(
     db: &DatabaseConnection,
     submission_id: i32,
-    submission: SubmissionPatch,
-) -> Result<entity::submission::Model, ExecutionError> {
+    mut submission: SubmissionPatch,
+    ctx: &Context,
+) -> Result<submission::Model, ExecutionError> {
+    // Ensure the submission exists.
+    let Some(current) = fetch_cities_submission(db, submission_id, None).await? else {
+        return Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find submission with id {submission_id}"),
+        ));
+    };
+
+    // The status can only be changed by reviewing the submission.
+    if submission
+        .status
+        .as_ref()
+        .is_some_and(|status| status != &current.status)
+    {
+        return Err(ExecutionError::InvalidAttribute(
+            ctx.request_id(),
+            "/status".to_string(),
+            "use the approve or reject actions to change the status of a submission".to_string(),
+        ));
+    }
+
+    // Validate the submission as it will be once patched.
+    let mut patched = SubmissionPost {
+        first_name: submission.first_name.clone().unwrap_or(current.first_name),
+        last_name: submission.last_name.clone().unwrap_or(current.last_name),
+        occupation: submission.occupation.clone().unwrap_or(current.occupation),
+        organization: submission
+            .organization
+            .clone()
+            .unwrap_or(current.organization),
+        email: submission.email.clone().unwrap_or(current.email),
+        country: submission.country.clone().unwrap_or(current.country),
+        city: submission.city.clone().unwrap_or(current.city),
+        region: submission.region.clone().unwrap_or(current.region),
+        fips_code: submission.fips_code.clone().unwrap_or(current.fips_code),
+        consent: submission.consent.unwrap_or(current.consent),
+        status: None,
+    };
+    validate_submission(db, &mut patched, ctx).await?;
+    if submission.country.is_some() {
+        submission.country = Some(patched.country);
+    }
+    if submission.region.is_some() {
+        submission.region = Some(patched.region);
+    }
+
     // Turn the wrapper into an active model.
     let mut active_model = submission.into_active_model();
     active_model.id = ActiveValue::Unchanged(submission_id);
 
+    // Update the entry.
     let model = active_model.update(db).await?;
     Ok(model)
 }
 
+pub async fn delete_cities_submission_adaptor(
+    db: &DatabaseConnection,
+    submission_id: i32,
+    ctx: &Context,
+) -> Result<(), ExecutionError> {
+    let res = delete_cities_submission(db, submission_id).await?;
+    match res.rows_affected {
+        0 => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find submission with id {submission_id}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reviews a submission, moving it to the `status` approval status.
+///
+/// The decision is recorded in the review history of the submission. Approving a
+/// submission also queues the analysis of the city and creates a pending BNA
+/// pipeline.
+pub async fn post_cities_submission_review_adaptor(
+    db: &DatabaseConnection,
+    queue: &impl Queue,
+    submission_id: i32,
+    status: ApprovalStatus,
+    note: Option<String>,
+    ctx: &Context,
+) -> Result<(submission::Model, Vec<submission_review::Model>), ExecutionError> {
+    // The reviews are recorded under the identity of the authenticated caller.
+    let reviewer = ctx
+        .principal()
+        .filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| {
+            ExecutionError::Unauthorized(
+                ctx.request_id(),
+                ctx.source(),
+                "the reviewer must be authenticated".to_string(),
+            )
+        })?;
+    // Approving a submission queues a paid analysis, so the reviews are reserved to the
+    // administrators, like the analysis requests.
+    if !ctx.is_admin() {
+        return Err(ExecutionError::Forbidden(
+            ctx.request_id(),
+            ctx.source(),
+            "only the administrators can review a submission".to_string(),
+        ));
+    }
+    let note = note.filter(|n| !n.trim().is_empty());
+    if status == ApprovalStatus::Rejected && note.is_none() {
+        return Err(ExecutionError::InvalidAttribute(
+            ctx.request_id(),
+            "/note".to_string(),
+            "a note is required to reject a submission".to_string(),
+        ));
+    }
+
+    // Validate the transition, locking the submission so that it cannot be reviewed
+    // concurrently.
+    let txn = db.begin().await?;
+    let Some(model) = fetch_cities_submission_for_update(&txn, submission_id).await? else {
+        return Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find submission with id {submission_id}"),
+        ));
+    };
+    let previous_status = ApprovalStatus::from_str(&model.status).map_err(|e| {
+        ExecutionError::Unexpected(
+            ctx.request_id(),
+            ctx.source(),
+            format!("invalid status for submission {submission_id}"),
+            e.to_string(),
+        )
+    })?;
+    if !previous_status.can_transition_to(&status) {
+        return Err(ExecutionError::Conflict(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot change the status of submission {submission_id} from {previous_status} to {status}"),
+        ));
+    }
+
+    // An approved submission must describe the city well enough to be analyzed.
+    let message = if status == ApprovalStatus::Approved {
+        let message =
+            AnalysisMessage::from_submission(Uuid::new_v4(), &model).ok_or_else(|| {
+                ExecutionError::Conflict(
+                    ctx.request_id(),
+                    ctx.source(),
+                    format!("submission {submission_id} must specify a region to be analyzed"),
+                )
+            })?;
+        Some(message)
+    } else {
+        None
+    };
+
+    // Update the submission and record the review.
+    submission::Entity::update_many()
+        .col_expr(submission::Column::Status, Expr::value(status.to_string()))
+        .filter(submission::Column::Id.eq(submission_id))
+        .exec(&txn)
+        .await?;
+    let review = SubmissionReviewPost {
+        submission_id,
+        reviewer,
+        previous_status: previous_status.to_string(),
+        status: status.to_string(),
+        note,
+    };
+    review.into_active_model().insert(&txn).await?;
+    let model = submission::Model {
+        status: status.to_string(),
+        ..model
+    };
+    record_event(
+        &txn,
+        WebhookEvent::SubmissionStatusChanged,
+        json!({
+            "submission_id": model.id,
+            "previous_status": previous_status,
+            "status": model.status,
+        }),
+    )
+    .await?;
+
+    // If the submission was approved, enqueue its analysis. The transaction ensures
+    // a pipeline is only recorded for a message which was sent.
+    if let Some(message) = message {
+        let pipeline = enqueue_analysis(&txn, queue, message).await?;
+        info!(
+            "analysis of submission {submission_id} queued for pipeline {}",
+            pipeline.state_machine_id
+        );
+    }
+    txn.commit().await?;
+
+    let reviews = fetch_cities_submission_reviews(db, submission_id).await?;
+    Ok((model, reviews))
+}
+
+pub async fn post_cities_analysis_adaptor(
+    db: &DatabaseConnection,
+    queue: &impl Queue,
+    country: &str,
+    region: &str,
+    name: &str,
+    version: Option<String>,
+    ctx: Context,
+) -> Result<bna_pipeline::Model, ExecutionError> {
+    if !ctx.is_admin() {
+        return Err(ExecutionError::Forbidden(
+            ctx.request_id(),
+            ctx.source(),
+            "only the administrators can request an analysis".to_string(),
+        ));
+    }
+
+    // Ensure the city exists and can be analyzed.
+    let city = get_city_adaptor(db, country, region, name, ctx.clone()).await?;
+    let Some(mut message) = AnalysisMessage::from_city(Uuid::new_v4(), &city) else {
+        return Err(ExecutionError::Conflict(
+            ctx.request_id(),
+            ctx.source(),
+            format!("city {} must have a FIPS code to be analyzed", city.id),
+        ));
+    };
+
+    // Enqueue a new analysis.
+    message.version = version;
+    let txn = db.begin().await?;
+    let pipeline = enqueue_analysis(&txn, queue, message).await?;
+    txn.commit().await?;
+    info!(
+        "analysis of city {} queued for pipeline {}",
+        city.id, pipeline.state_machine_id
+    );
+
+    Ok(pipeline)
+}
+
+/// Records a pending pipeline for the analysis and sends its message to the queue.
+///
+/// The pipeline is identified by the pipeline id of the message. It is inserted
+/// first, so that the caller can roll back its transaction if the message cannot be
+/// sent.
+async fn enqueue_analysis(
+    db: &impl ConnectionTrait,
+    queue: &impl Queue,
+    message: AnalysisMessage,
+) -> Result<bna_pipeline::Model, ExecutionError> {
+    let pipeline = BNAPipelinePost {
+        cost: None,
+        end_time: None,
+        fargate_price_id: None,
+        fargate_task_arn: None,
+        result_posted: None,
+        s3_bucket: None,
+        sqs_message: Some(serde_json::to_value(&message).map_err(QueueError::from)?),
+        start_time: None,
+        state_machine_id: message.pipeline_id,
+    };
+    let pipeline = pipeline.into_active_model().insert(db).await?;
+    let message_id = queue.send(&message).await?;
+    info!(
+        "pipeline {} queued with message {message_id}",
+        pipeline.state_machine_id
+    );
+
+    Ok(pipeline)
+}
+
 pub async fn post_cities_submission_adaptor(
     db: &DatabaseConnection,
-    submission: SubmissionPost,
+    mut submission: SubmissionPost,
+    ctx: Context,
 ) -> Result<entity::submission::Model, ExecutionError> {
-    // note(rgreinho): Leaving it commented out here because this check is still under
-    // discussion.
-    // Ensure the country is a valid one.
-    // if fetch_country(&db, &submission.country).await?.is_none() {
-    //     return Err(ExecutionError::UncoveredCountry(submission.country));
-    // }
+    // Validate the payload, and use the canonical names of the country and the US
+    // states.
+    validate_submission(db, &mut submission, &ctx).await?;
+
+    // Ensure the city was not already analyzed or requested. The FIPS code is only
+    // meaningful for the US cities, "0" being used for the other ones. The city stays
+    // locked until the submission is inserted, so that it can only be submitted once.
+    let fips_code = Some(submission.fips_code.trim()).filter(|f| !f.is_empty() && *f != "0");
+    let region = submission
+        .region
+        .clone()
+        .unwrap_or_else(|| submission.country.clone());
+    let txn = db.begin().await?;
+    lock_submission_location(
+        &txn,
+        fips_code,
+        &submission.country,
+        &region,
+        &submission.city,
+    )
+    .await?;
+    if let Some(city) = fetch_matching_city(
+        &txn,
+        fips_code,
+        &submission.country,
+        &region,
+        &submission.city,
+    )
+    .await?
+    {
+        return Err(ExecutionError::Conflict(
+            ctx.request_id(),
+            format!("/cities/{}/{}/{}", city.country, city.state, city.name),
+            format!(
+                "the city {}, {}, {} already exists",
+                city.name, city.state, city.country
+            ),
+        ));
+    }
+    if let Some(existing) = fetch_matching_open_submission(
+        &txn,
+        fips_code,
+        &submission.country,
+        &region,
+        &submission.city,
+    )
+    .await?
+    {
+        return Err(ExecutionError::Conflict(
+            ctx.request_id(),
+            format!("/cities/submissions/{}", existing.id),
+            format!(
+                "the city was already submitted and its submission is {}",
+                existing.status.to_lowercase()
+            ),
+        ));
+    }
 
     // Turn the post model into an active model.
     let mut active_model: entity::submission::ActiveModel = submission.into_active_model();
@@ -175,11 +548,231 @@ pub async fn post_cities_submission_adaptor(
     // Add the status.
     active_model.status = ActiveValue::Set("Pending".to_string());
 
-    // And insert a new entry.
-    let model = active_model.insert(db).await?;
+    // And insert a new entry. The submissions are not cached, therefore the cache is
+    // left untouched.
+    let model = active_model.insert(&txn).await?;
+    txn.commit().await?;
     Ok(model)
 }
 
+/// Exports every submission made with an email address, with their review history.
+pub async fn post_cities_submissions_export_adaptor(
+    db: &DatabaseConnection,
+    email: &str,
+    ctx: Context,
+) -> Result<Vec<(submission::Model, Vec<submission_review::Model>)>, ExecutionError> {
+    validate_personal_data_request(email, &ctx)?;
+    let submissions = fetch_cities_submissions_by_email(db, email).await?;
+    let mut export = Vec::with_capacity(submissions.len());
+    for submission in submissions {
+        let reviews = fetch_cities_submission_reviews(db, submission.id).await?;
+        export.push((submission, reviews));
+    }
+    info!("{} submission(s) exported", export.len());
+
+    Ok(export)
+}
+
+/// Anonymizes every submission made with an email address.
+///
+/// The city requests are kept, only the personal data of the submitter is erased.
+pub async fn post_cities_submissions_erasure_adaptor(
+    db: &DatabaseConnection,
+    email: &str,
+    ctx: Context,
+) -> Result<u64, ExecutionError> {
+    validate_personal_data_request(email, &ctx)?;
+    let erased = erase_cities_submissions_by_email(db, email, ERASED).await?;
+    info!("{erased} submission(s) anonymized");
+
+    Ok(erased)
+}
+
+/// Ensures the caller is allowed to access the personal data, and that the email
+/// address is valid.
+fn validate_personal_data_request(email: &str, ctx: &Context) -> Result<(), ExecutionError> {
+    if !ctx.is_admin() {
+        return Err(ExecutionError::Forbidden(
+            ctx.request_id(),
+            ctx.source(),
+            "only the administrators can access the personal data".to_string(),
+        ));
+    }
+    let mut validator = Validator::new(ctx.request_id());
+    validator.check(is_email(email), "/email", "the email address is invalid");
+    validator.finish()
+}
+
+/// Validates a new city, reporting all the invalid attributes at once.
+///
+/// Returns the US state of the city, if it is located in the United States.
+async fn validate_city(
+    db: &DatabaseConnection,
+    city: &mut CityPost,
+    ctx: &Context,
+) -> Result<(), ExecutionError> {
+    let mut validator = Validator::new(ctx.request_id());
+    validator
+        .check(is_present(&city.name), "/name", "the city name is required")
+        .check(
+            city.latitude.is_none_or(is_latitude),
+            "/latitude",
+            "the latitude must be between -90 and 90",
+        )
+        .check(
+            city.longitude.is_none_or(is_longitude),
+            "/longitude",
+            "the longitude must be between -180 and 180",
+        )
+        .check(
+            city.residential_speed_limit.is_none_or(|s| s > 0),
+            "/residential_speed_limit",
+            "the residential speed limit must be positive",
+        );
+    if let Some(country) = validate_country(db, &mut validator, &city.country).await? {
+        city.country = country.name;
+    }
+    if is_united_states(&city.country) {
+        if let Some(us_state) = validate_us_location(
+            db,
+            &mut validator,
+            Some(&city.state),
+            "/state",
+            city.fips_code.as_deref(),
+        )
+        .await?
+        {
+            city.state = us_state.name;
+        }
+    } else {
+        validator.check(is_present(&city.state), "/state", "the state is required");
+    }
+    validator.finish()
+}
+
+/// Validates a submission, reporting all the invalid attributes at once.
+///
+/// The submissions of US cities require the census place code of the city, "0" being
+/// only accepted for the cities outside of the United States.
+async fn validate_submission(
+    db: &DatabaseConnection,
+    submission: &mut SubmissionPost,
+    ctx: &Context,
+) -> Result<(), ExecutionError> {
+    let mut validator = Validator::new(ctx.request_id());
+    validator
+        .check(
+            is_present(&submission.first_name),
+            "/first_name",
+            "the first name is required",
+        )
+        .check(
+            is_present(&submission.last_name),
+            "/last_name",
+            "the last name is required",
+        )
+        .check(
+            is_email(&submission.email),
+            "/email",
+            "the email address is invalid",
+        )
+        .check(
+            is_present(&submission.city),
+            "/city",
+            "the city name is required",
+        )
+        .check(
+            submission.consent,
+            "/consent",
+            "the consent is required to process the submission",
+        );
+    if let Some(country) = validate_country(db, &mut validator, &submission.country).await? {
+        submission.country = country.name;
+    }
+    if is_united_states(&submission.country) {
+        if let Some(us_state) = validate_us_location(
+            db,
+            &mut validator,
+            submission.region.as_deref(),
+            "/region",
+            Some(&submission.fips_code),
+        )
+        .await?
+        {
+            submission.region = Some(us_state.name);
+        }
+    } else {
+        validator.check(
+            submission.fips_code == "0",
+            "/fips_code",
+            "the FIPS code must be 0 for the cities outside of the United States",
+        );
+    }
+
+    validator.finish()
+}
+
+/// Ensures the country is covered by the analyzer, ignoring the case.
+async fn validate_country(
+    db: &DatabaseConnection,
+    validator: &mut Validator,
+    country: &str,
+) -> Result<Option<country::Model>, ExecutionError> {
+    let model = fetch_country(db, country).await?;
+    if model.is_none() {
+        validator.error(
+            "/country",
+            &format!("the country is not covered by the analyzer: {country}"),
+        );
+    }
+    Ok(model)
+}
+
+/// Ensures the state of a US city exists, and that its FIPS code, if any, is a place
+/// code within this state.
+async fn validate_us_location(
+    db: &DatabaseConnection,
+    validator: &mut Validator,
+    state: Option<&str>,
+    state_pointer: &str,
+    fips_code: Option<&str>,
+) -> Result<Option<us_state::Model>, ExecutionError> {
+    if let Some(fips_code) = fips_code {
+        validator.check(
+            is_fips_place_code(fips_code),
+            "/fips_code",
+            "the FIPS code must be a 7-digit U.S. census place code",
+        );
+    }
+    let Some(state) = state.filter(|s| is_present(s)) else {
+        validator.error(
+            state_pointer,
+            "the state is required for the cities of the United States",
+        );
+        return Ok(None);
+    };
+    let Some(us_state) = fetch_us_state_by_name(db, state).await? else {
+        validator.error(
+            state_pointer,
+            &format!("the state is not a valid US state: {state}"),
+        );
+        return Ok(None);
+    };
+    if let Some(fips_code) = fips_code.filter(|f| is_fips_place_code(f)) {
+        validator.check(
+            fips_code.starts_with(&us_state.fips_code),
+            "/fips_code",
+            &format!("the FIPS code is not a place of {}", us_state.name),
+        );
+    }
+
+    Ok(Some(us_state))
+}
+
+fn is_united_states(country: &str) -> bool {
+    country.to_lowercase().eq("united states")
+}
+
 pub(crate) async fn get_top_cities_adaptor(
     db: &DatabaseConnection,
     year: i32,
@@ -187,7 +780,8 @@ pub(crate) async fn get_top_cities_adaptor(
     ctx: Context,
 ) -> Result<Vec<(city::Model, summary::Model)>, ExecutionError> {
     // Fetch the top cities and their associated summaries.
-    let model = match fetch_top_cities(db, year, count).await {
+    let key = format!("/cities/top/{year}/{count}");
+    let model = match cached("top_cities", &key, || fetch_top_cities(db, year, count)).await {
         Ok(model) => model,
         Err(e) => {
             info!("{e:?}");
@@ -212,5 +806,11 @@ pub async fn get_cities_latest_summary_adaptor(
     page_size: u64,
 ) -> Result<(u64, Vec<(city::Model, summary::Model)>), ExecutionError> {
     // Fetch all cities with their latest summary.
-    Ok(fetch_cities_with_latest_summary(db, sort_direction, sort_by, page, page_size).await?)
+    let key = format!(
+        "/cities/summary/latest?order_direction={sort_direction:?}&sort_by={sort_by}&page={page}&page_size={page_size}"
+    );
+    Ok(cached("cities_summary", &key, || {
+        fetch_cities_with_latest_summary(db, sort_direction, sort_by, page, page_size)
+    })
+    .await?)
 }
@@ -1,8 +1,14 @@
+use super::{normalize_location, SubmissionFilter};
 use crate::core::resource::schema::OrderDirection;
-use entity::{city, country, state_region_crosswalk, submission, summary};
+use entity::{
+    city, country, state_region_crosswalk, submission, submission_review, summary, us_state,
+    wrappers::ApprovalStatus,
+};
 use sea_orm::{
-    ColumnTrait, Condition, DatabaseBackend, DatabaseConnection, DbErr, EntityTrait, LoaderTrait,
-    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
+    sea_query::{Expr, Func, IntoColumnRef},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr,
+    DeleteResult, EntityTrait, LoaderTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    Statement,
 };
 
 pub(crate) fn city_column(header: &str) -> city::Column {
@@ -78,7 +84,7 @@ pub(crate) async fn fetch_country(
     country: &str,
 ) -> Result<Option<country::Model>, DbErr> {
     country::Entity::find()
-        .filter(country::Column::Name.eq(country))
+        .filter(lower(country::Column::Name).eq(normalize_location(country)))
         .one(db)
         .await
 }
@@ -93,6 +99,17 @@ pub(crate) async fn fetch_state_region_crosswalk(
         .await
 }
 
+/// Fetches a US state by name, ignoring the case.
+pub(crate) async fn fetch_us_state_by_name(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<Option<us_state::Model>, DbErr> {
+    us_state::Entity::find()
+        .filter(lower(us_state::Column::Name).eq(normalize_location(name)))
+        .one(db)
+        .await
+}
+
 pub(crate) async fn fetch_cities_submission(
     db: &DatabaseConnection,
     submission_id: i32,
@@ -111,22 +128,216 @@ pub(crate) async fn fetch_cities_submission(
         .await
 }
 
+/// Fetches a submission and locks it until the end of the transaction.
+pub(crate) async fn fetch_cities_submission_for_update(
+    db: &impl ConnectionTrait,
+    submission_id: i32,
+) -> Result<Option<submission::Model>, DbErr> {
+    submission::Entity::find_by_id(submission_id)
+        .lock_exclusive()
+        .one(db)
+        .await
+}
+
+/// Fetches the city matching either the FIPS code or the normalized location.
+pub(crate) async fn fetch_matching_city(
+    db: &impl ConnectionTrait,
+    fips_code: Option<&str>,
+    country: &str,
+    state: &str,
+    name: &str,
+) -> Result<Option<city::Model>, DbErr> {
+    let mut conditions = Condition::any().add(
+        Condition::all()
+            .add(lower(city::Column::Country).eq(normalize_location(country)))
+            .add(lower(city::Column::State).eq(normalize_location(state)))
+            .add(lower(city::Column::Name).eq(normalize_location(name))),
+    );
+    if let Some(fips_code) = fips_code {
+        conditions = conditions.add(city::Column::FipsCode.eq(fips_code));
+    }
+    city::Entity::find().filter(conditions).one(db).await
+}
+
+/// Fetches the oldest open submission matching either the FIPS code or the
+/// normalized location.
+///
+/// A submission is open while it is pending, or approved but not analyzed yet.
+pub(crate) async fn fetch_matching_open_submission(
+    db: &impl ConnectionTrait,
+    fips_code: Option<&str>,
+    country: &str,
+    region: &str,
+    name: &str,
+) -> Result<Option<submission::Model>, DbErr> {
+    // Countries without regions use the country name instead.
+    let submission_region = Func::lower(Func::coalesce([
+        Expr::col(submission::Column::Region).into(),
+        Expr::col(submission::Column::Country).into(),
+    ]));
+    let mut location = Condition::any().add(
+        Condition::all()
+            .add(lower(submission::Column::Country).eq(normalize_location(country)))
+            .add(Expr::expr(submission_region).eq(normalize_location(region)))
+            .add(lower(submission::Column::City).eq(normalize_location(name))),
+    );
+    if let Some(fips_code) = fips_code {
+        location = location.add(submission::Column::FipsCode.eq(fips_code));
+    }
+    submission::Entity::find()
+        .filter(location)
+        .filter(submission::Column::Status.is_in([
+            ApprovalStatus::Pending.to_string(),
+            ApprovalStatus::Approved.to_string(),
+        ]))
+        .order_by_asc(submission::Column::CreatedAt)
+        .one(db)
+        .await
+}
+
+/// Locks the submissions of a city until the end of the transaction.
+///
+/// Two concurrent submissions of the same city would otherwise both pass the duplicate
+/// checks before any of them is inserted. The lock is taken on the normalized location
+/// and, for the US cities, on the FIPS code, always in that order.
+pub(crate) async fn lock_submission_location(
+    db: &impl ConnectionTrait,
+    fips_code: Option<&str>,
+    country: &str,
+    region: &str,
+    name: &str,
+) -> Result<(), DbErr> {
+    let location = format!(
+        "submission:{}/{}/{}",
+        normalize_location(country),
+        normalize_location(region),
+        normalize_location(name)
+    );
+    let keys = std::iter::once(location).chain(fips_code.map(|f| format!("submission:{f}")));
+    for key in keys {
+        db.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT pg_advisory_xact_lock(hashtext($1))",
+            vec![key.into()],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Builds the expression of a lowercased column.
+fn lower(column: impl IntoColumnRef) -> Expr {
+    Expr::expr(Func::lower(Expr::col(column)))
+}
+
+/// Fetches all the submissions made with an email address, ignoring the case.
+pub(crate) async fn fetch_cities_submissions_by_email(
+    db: &DatabaseConnection,
+    email: &str,
+) -> Result<Vec<submission::Model>, DbErr> {
+    submission::Entity::find()
+        .filter(lower(submission::Column::Email).eq(email.trim().to_lowercase()))
+        .order_by_asc(submission::Column::CreatedAt)
+        .all(db)
+        .await
+}
+
+/// Domain of the tombstones replacing the erased email addresses, reserved by RFC 2606
+/// so that they can never be delivered.
+const ERASED_EMAIL_DOMAIN: &str = "@erased.invalid";
+
+/// Anonymizes the personal data of all the submissions made with an email address,
+/// keeping the city requests themselves.
+///
+/// The names are replaced with the `erased` placeholder, and the email address with a
+/// tombstone unique to each submission, i.e. `erased-42@erased.invalid`, so that the
+/// anonymized submissions are not grouped under a common address.
+///
+/// Returns the number of anonymized submissions.
+pub(crate) async fn erase_cities_submissions_by_email(
+    db: &DatabaseConnection,
+    email: &str,
+    erased: &str,
+) -> Result<u64, DbErr> {
+    let result = submission::Entity::update_many()
+        .col_expr(submission::Column::FirstName, Expr::value(erased))
+        .col_expr(submission::Column::LastName, Expr::value(erased))
+        .col_expr(
+            submission::Column::Email,
+            Expr::cust_with_exprs(
+                "$1 || CAST($2 AS TEXT) || $3",
+                [
+                    Expr::value("erased-"),
+                    Expr::col(submission::Column::Id).into(),
+                    Expr::value(ERASED_EMAIL_DOMAIN),
+                ],
+            ),
+        )
+        .col_expr(
+            submission::Column::Occupation,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            submission::Column::Organization,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(lower(submission::Column::Email).eq(email.trim().to_lowercase()))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Deletes a submission, along with its review history.
+pub(crate) async fn delete_cities_submission(
+    db: &DatabaseConnection,
+    submission_id: i32,
+) -> Result<DeleteResult, DbErr> {
+    submission::Entity::delete_by_id(submission_id)
+        .exec(db)
+        .await
+}
+
+/// Fetches the review history of a submission, oldest first.
+pub(crate) async fn fetch_cities_submission_reviews(
+    db: &impl ConnectionTrait,
+    submission_id: i32,
+) -> Result<Vec<submission_review::Model>, DbErr> {
+    submission_review::Entity::find()
+        .filter(submission_review::Column::SubmissionId.eq(submission_id))
+        .order_by_asc(submission_review::Column::CreatedAt)
+        .order_by_asc(submission_review::Column::Id)
+        .all(db)
+        .await
+}
+
+pub(crate) fn submission_column(header: &str) -> submission::Column {
+    match header {
+        "city" => submission::Column::City,
+        "country" => submission::Column::Country,
+        _ => submission::Column::CreatedAt,
+    }
+}
+
 pub(crate) async fn fetch_cities_submissions(
     db: &DatabaseConnection,
-    status: Option<String>,
+    filter: &SubmissionFilter,
+    sort_direction: OrderDirection,
+    sort_by: &str,
     page: u64,
     page_size: u64,
 ) -> Result<(u64, Vec<entity::submission::Model>), DbErr> {
     // Filter the query if needed.
-    let mut conditions = Condition::all();
-    if let Some(status) = status {
+    let mut conditions = submission_conditions(filter);
+    if let Some(status) = &filter.status {
         conditions = conditions.add(entity::submission::Column::Status.eq(status))
     }
 
-    // Select the submissions.
+    // Select the submissions. The identifier keeps the order stable across pages.
     let select = submission::Entity::find().filter(conditions);
     let models = select
         .clone()
+        .order_by(submission_column(sort_by), sort_direction.into())
+        .order_by_asc(submission::Column::Id)
         .paginate(db, page_size)
         .fetch_page(page)
         .await?;
@@ -134,6 +345,73 @@ pub(crate) async fn fetch_cities_submissions(
     Ok((count, models))
 }
 
+/// Counts the submissions per approval status.
+///
+/// All the filters except the status one are applied.
+pub(crate) async fn count_cities_submissions_by_status(
+    db: &DatabaseConnection,
+    filter: &SubmissionFilter,
+) -> Result<Vec<(String, i64)>, DbErr> {
+    submission::Entity::find()
+        .select_only()
+        .column(submission::Column::Status)
+        .column_as(submission::Column::Id.count(), "count")
+        .filter(submission_conditions(filter))
+        .group_by(submission::Column::Status)
+        .into_tuple::<(String, i64)>()
+        .all(db)
+        .await
+}
+
+/// Builds the conditions matching the submission filters, except the status.
+fn submission_conditions(filter: &SubmissionFilter) -> Condition {
+    let mut conditions = Condition::all();
+    if let Some(country) = &filter.country {
+        conditions =
+            conditions.add(lower(submission::Column::Country).eq(normalize_location(country)));
+    }
+    if let Some(region) = &filter.region {
+        conditions =
+            conditions.add(lower(submission::Column::Region).eq(normalize_location(region)));
+    }
+    if let Some(created_after) = filter.created_after {
+        conditions = conditions.add(submission::Column::CreatedAt.gte(created_after));
+    }
+    if let Some(created_before) = filter.created_before {
+        conditions = conditions.add(submission::Column::CreatedAt.lte(created_before));
+    }
+    if let Some(email) = &filter.email {
+        conditions =
+            conditions.add(lower(submission::Column::Email).eq(email.trim().to_lowercase()));
+    }
+    if let Some(q) = filter.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", escape_like(&q.to_lowercase()));
+        let mut text = Condition::any().add(lower(submission::Column::City).like(pattern.as_str()));
+        if filter.personal_data {
+            text = [
+                submission::Column::FirstName,
+                submission::Column::LastName,
+                submission::Column::Email,
+                submission::Column::Organization,
+            ]
+            .into_iter()
+            .fold(text, |text, column| {
+                text.add(lower(column).like(pattern.as_str()))
+            });
+        }
+        conditions = conditions.add(text);
+    }
+    conditions
+}
+
+/// Escapes the wildcards of a `LIKE` pattern.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 pub(crate) async fn fetch_top_cities(
     db: &DatabaseConnection,
     year: i32,
@@ -1,40 +1,61 @@
 use super::{
     adaptor::{
-        get_cities_adaptor, get_cities_latest_summary_adaptor, get_cities_ratings_adaptor,
-        get_cities_submission_adaptor, get_cities_submissions_adaptor, get_city_adaptor,
-        patch_cities_submission_adaptor, post_cities_adaptor, post_cities_submission_adaptor,
+        delete_cities_submission_adaptor, get_cities_adaptor, get_cities_latest_summary_adaptor,
+        get_cities_ratings_adaptor, get_cities_submission_adaptor, get_cities_submissions_adaptor,
+        get_city_adaptor, patch_cities_submission_adaptor, post_cities_adaptor,
+        post_cities_analysis_adaptor, post_cities_submission_adaptor,
+        post_cities_submission_review_adaptor, post_cities_submissions_erasure_adaptor,
+        post_cities_submissions_export_adaptor,
     },
     schema::{
-        Cities, CitiesWithSummary, CityPost, CityRatings, RatingSummary, Submission,
-        SubmissionPatch, SubmissionPost, Submissions,
+        AnalysisPost, Cities, CitiesWithSummary, CityPost, CityRatings, PersonalDataErasure,
+        PersonalDataExport, PersonalDataRequest, RatingSummary, Submission, SubmissionPatch,
+        SubmissionPost, SubmissionReviewPost, Submissions,
     },
 };
 use crate::{
-    core::resource::{
-        cities::{
-            adaptor::get_top_cities_adaptor,
-            schema::{CityParams, CityWithSummary},
-            CitiesPathParameters,
+    core::{
+        config::config,
+        extract::{Json, Path, Query},
+        http_cache::last_modified,
+        metrics,
+        queue::analysis_queue_or_init,
+        resource::{
+            cities::{
+                adaptor::get_top_cities_adaptor,
+                schema::{CityParams, CityWithSummary},
+                CitiesPathParameters, SubmissionFilter,
+            },
+            pipelines::schema::{pipeline_schema, BnaPipeline, BnaPipelineV2},
+            schema::{APIErrors, City, ErrorResponses, ListParameters, PaginationParameters},
         },
-        schema::{City, ErrorResponses, ListParameters, PaginationParameters},
+        versioning::ApiVersion,
     },
-    database_connect_or_init, Context, ExecutionError, PageFlow, Paginatron,
+    database_connect_or_init, database_read_connect_or_init, Context, ExecutionError, PageFlow,
+    Paginatron,
 };
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    Json,
+    http::{header, HeaderName, StatusCode},
+    response::AppendHeaders,
 };
-use axum_extra::extract::OptionalQuery;
-use entity::wrappers::{city, submission};
+use chrono::{DateTime, FixedOffset};
+use entity::wrappers::{city, submission, ApprovalStatus};
 use serde::{self, Deserialize};
 use tracing::debug;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 const TAG: &str = "city";
-const TOP_CITIES_MAX_COUNT: u64 = 100;
 
 pub fn routes() -> OpenApiRouter {
+    shared_routes().routes(routes!(v1::post_cities_analysis))
+}
+
+pub fn routes_v2() -> OpenApiRouter {
+    shared_routes().routes(routes!(v2::post_cities_analysis))
+}
+
+/// Routes identical in every version of the API.
+fn shared_routes() -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(get_city))
         .routes(routes!(post_city))
@@ -44,6 +65,11 @@ pub fn routes() -> OpenApiRouter {
         .routes(routes!(get_cities_submission))
         .routes(routes!(post_cities_submission))
         .routes(routes!(patch_cities_submission))
+        .routes(routes!(delete_cities_submission))
+        .routes(routes!(post_cities_submission_approve))
+        .routes(routes!(post_cities_submission_reject))
+        .routes(routes!(post_cities_submissions_export))
+        .routes(routes!(post_cities_submissions_erasure))
         .routes(routes!(get_cities_submissions))
         .routes(routes!(get_top_cities))
 }
@@ -64,11 +90,14 @@ async fn get_city(
     Path(params): Path<CitiesPathParameters>,
     ctx: Context,
 ) -> Result<Json<City>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_city_adaptor(db, &params.country, &params.region, &params.name, ctx)
-        .await
-        .map(City::from)
-        .map(Json)
+    let db = &database_read_connect_or_init().await?;
+    metrics::adaptor(
+        "get_city_adaptor",
+        get_city_adaptor(db, &params.country, &params.region, &params.name, ctx),
+    )
+    .await
+    .map(City::from)
+    .map(Json)
 }
 
 #[axum::debug_handler]
@@ -86,13 +115,16 @@ async fn get_city(
 async fn get_cities(
     Query(list): Query<ListParameters>,
 ) -> Result<PageFlow<Cities>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let payload = get_cities_adaptor(
-        db,
-        list.order_direction(),
-        &list.sort_by(),
-        list.page(),
-        list.page_size(),
+    let db = &database_read_connect_or_init().await?;
+    let payload = metrics::adaptor(
+        "get_cities_adaptor",
+        get_cities_adaptor(
+            db,
+            list.order_direction(),
+            &list.sort_by(),
+            list.page(),
+            list.page_size(),
+        ),
     )
     .await?;
     Ok(PageFlow::new(
@@ -114,14 +146,23 @@ async fn get_cities(
   ))]
 async fn get_cities_latest_summary(
     Query(list): Query<ListParameters>,
-) -> Result<PageFlow<CitiesWithSummary>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let payload = get_cities_latest_summary_adaptor(
-        db,
-        list.order_direction(),
-        &list.sort_by(),
-        list.page(),
-        list.page_size(),
+) -> Result<
+    (
+        AppendHeaders<Option<(HeaderName, String)>>,
+        PageFlow<CitiesWithSummary>,
+    ),
+    ExecutionError,
+> {
+    let db = &database_read_connect_or_init().await?;
+    let payload = metrics::adaptor(
+        "get_cities_latest_summary_adaptor",
+        get_cities_latest_summary_adaptor(
+            db,
+            list.order_direction(),
+            &list.sort_by(),
+            list.page(),
+            list.page_size(),
+        ),
     )
     .await
     .map_err(|e| {
@@ -129,6 +170,13 @@ async fn get_cities_latest_summary(
         e
     })?;
 
+    // The page was last modified with its most recent city or summary.
+    let modified = last_modified(
+        payload
+            .1
+            .iter()
+            .flat_map(|(c, s)| [c.updated_at.unwrap_or(c.created_at), s.created_at]),
+    );
     let cities_with_summaries = payload
         .1
         .iter()
@@ -138,9 +186,12 @@ async fn get_cities_latest_summary(
         })
         .collect::<Vec<CityWithSummary>>();
 
-    Ok(PageFlow::new(
-        Paginatron::new(None, payload.0, list.page(), list.page_size()),
-        CitiesWithSummary(cities_with_summaries),
+    Ok((
+        AppendHeaders(modified),
+        PageFlow::new(
+            Paginatron::new(None, payload.0, list.page(), list.page_size()),
+            CitiesWithSummary(cities_with_summaries),
+        ),
     ))
 }
 
@@ -162,15 +213,18 @@ async fn get_city_ratings(
     Query(pagination): Query<PaginationParameters>,
     ctx: Context,
 ) -> Result<PageFlow<CityRatings>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let city_ratings = get_cities_ratings_adaptor(
-        db,
-        &params.country,
-        &params.region,
-        &params.name,
-        pagination.page(),
-        pagination.page_size(),
-        ctx,
+    let db = &database_read_connect_or_init().await?;
+    let city_ratings = metrics::adaptor(
+        "get_cities_ratings_adaptor",
+        get_cities_ratings_adaptor(
+            db,
+            &params.country,
+            &params.region,
+            &params.name,
+            pagination.page(),
+            pagination.page_size(),
+            ctx,
+        ),
     )
     .await?;
     let city = city_ratings.1.first().unwrap().0.clone();
@@ -206,10 +260,11 @@ async fn get_city_ratings(
     ErrorResponses,
   ))]
 async fn post_city(
+    ctx: Context,
     Json(city): Json<city::CityPost>,
 ) -> Result<(StatusCode, Json<City>), ExecutionError> {
-    let db = database_connect_or_init().await?;
-    post_cities_adaptor(db, city)
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor("post_cities_adaptor", post_cities_adaptor(db, city, ctx))
         .await
         .map_err(|e| {
             debug!("{e}");
@@ -234,60 +289,136 @@ async fn post_city(
   ))]
 async fn get_cities_submission(
     Path(submission_id): Path<i32>,
-    OptionalQuery(status): OptionalQuery<String>,
+    Query(params): Query<SubmissionStatusParameter>,
     ctx: Context,
 ) -> Result<Json<Submission>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_cities_submission_adaptor(db, submission_id, status, ctx)
-        .await
-        .map(Submission::from)
-        .map(Json)
+    let db = &database_read_connect_or_init().await?;
+    metrics::adaptor(
+        "get_cities_submission_adaptor",
+        get_cities_submission_adaptor(db, submission_id, params.status, &ctx),
+    )
+    .await
+    .map(|(submission, reviews)| redact(Submission::with_reviews(submission, reviews), &ctx))
+    .map(Json)
+}
+
+/// Masks the personal data of a submission unless the caller is an administrator.
+fn redact(submission: Submission, ctx: &Context) -> Submission {
+    if ctx.is_admin() {
+        submission
+    } else {
+        submission.redacted()
+    }
+}
+
+#[derive(Deserialize)]
+struct SubmissionStatusParameter {
+    pub status: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct SubmissionParameters {
     pub status: Option<String>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub created_after: Option<DateTime<FixedOffset>>,
+    pub created_before: Option<DateTime<FixedOffset>>,
+    pub email: Option<String>,
+    pub q: Option<String>,
+}
+
+impl From<SubmissionParameters> for SubmissionFilter {
+    fn from(value: SubmissionParameters) -> Self {
+        Self {
+            status: value.status,
+            country: value.country,
+            region: value.region,
+            created_after: value.created_after,
+            created_before: value.created_before,
+            email: value.email,
+            q: value.q,
+            personal_data: false,
+        }
+    }
 }
 
 #[utoipa::path(
     get,
     path = "/cities/submissions",
-    description = "Get the submissions details.",
+    description = "Get the submissions details. The number of submissions per status, matching all the filters but the status one, is returned in the `x-total-<status>` headers.",
     tag = TAG,
     params(
       ("status" = Option<str>, Query, description = "Filter for the submission status", example = "Pending"),
-      PaginationParameters,
+      ("country" = Option<str>, Query, description = "Filter for the country, ignoring the case", example = "Belgium"),
+      ("region" = Option<str>, Query, description = "Filter for the region, ignoring the case", example = "Antwerp"),
+      ("created_after" = Option<DateTime<FixedOffset>>, Query, description = "Only the submissions created at or after this date", example = "2026-01-01T00:00:00Z"),
+      ("created_before" = Option<DateTime<FixedOffset>>, Query, description = "Only the submissions created at or before this date", example = "2026-12-31T23:59:59Z"),
+      ("email" = Option<str>, Query, description = "Filter for the email of the submitter, ignoring the case. Restricted to the administrators", example = "jane.doe@orgllc.com"),
+      ("q" = Option<str>, Query, description = "Free text search on the city name, and on the personal data of the submitter for the administrators", example = "antw"),
+      ListParameters,
     ),
     responses(
-      (status = OK, description = "Fetches submissions", body = Submissions),
+      (status = OK, description = "Fetches submissions", body = Submissions,
+        headers(
+          ("x-total-pending" = u64, description = "Number of pending submissions"),
+          ("x-total-approved" = u64, description = "Number of approved submissions"),
+          ("x-total-rejected" = u64, description = "Number of rejected submissions"),
+        )
+      ),
       ErrorResponses,
     ))]
 async fn get_cities_submissions(
     Query(submission_params): Query<SubmissionParameters>,
-    Query(pagination): Query<PaginationParameters>,
-) -> Result<PageFlow<Submissions>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let cities_submissions = get_cities_submissions_adaptor(
-        db,
-        submission_params.status,
-        pagination.page(),
-        pagination.page_size(),
+    Query(list): Query<ListParameters>,
+    ctx: Context,
+) -> Result<([(HeaderName, u64); 3], PageFlow<Submissions>), ExecutionError> {
+    let db = &database_read_connect_or_init().await?;
+    let (total, models, status_counts) = metrics::adaptor(
+        "get_cities_submissions_adaptor",
+        get_cities_submissions_adaptor(
+            db,
+            submission_params.into(),
+            list.order_direction(),
+            &list.sort_by(),
+            list.page(),
+            list.page_size(),
+            &ctx,
+        ),
     )
     .await?;
-    let submissions = cities_submissions
-        .1
+    let submissions = models
         .into_iter()
-        .map(Submission::from)
+        .map(|submission| redact(Submission::from(submission), &ctx))
         .collect::<Vec<Submission>>();
     let payload = Submissions(submissions);
-    Ok(PageFlow::new(
-        Paginatron::new(
-            None,
-            cities_submissions.0,
-            pagination.page(),
-            pagination.page_size(),
+
+    // Expose the number of submissions per status.
+    let count = |status: ApprovalStatus| {
+        status_counts
+            .iter()
+            .find(|(s, _)| *s == status.to_string())
+            .map_or(0, |(_, c)| *c as u64)
+    };
+    let headers = [
+        (
+            HeaderName::from_static("x-total-pending"),
+            count(ApprovalStatus::Pending),
+        ),
+        (
+            HeaderName::from_static("x-total-approved"),
+            count(ApprovalStatus::Approved),
+        ),
+        (
+            HeaderName::from_static("x-total-rejected"),
+            count(ApprovalStatus::Rejected),
+        ),
+    ];
+    Ok((
+        headers,
+        PageFlow::new(
+            Paginatron::new(None, total, list.page(), list.page_size()),
+            payload,
         ),
-        payload,
     ))
 }
 
@@ -299,26 +430,31 @@ async fn get_cities_submissions(
   request_body = SubmissionPost,
   responses(
     (status = CREATED, description = "Creates a new city submission", body = Submission),
+    (status = CONFLICT, description = "The city already exists or was already submitted. The source of the error points to the existing record", body = APIErrors),
     ErrorResponses,
   ))]
 async fn post_cities_submission(
+    ctx: Context,
     Json(submission): Json<submission::SubmissionPost>,
 ) -> Result<(StatusCode, Json<Submission>), ExecutionError> {
-    let db = database_connect_or_init().await?;
-    post_cities_submission_adaptor(db, submission)
-        .await
-        .map_err(|e| {
-            debug!("{e}");
-            e
-        })
-        .map(Submission::from)
-        .map(|v| (StatusCode::CREATED, Json(v)))
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "post_cities_submission_adaptor",
+        post_cities_submission_adaptor(db, submission, ctx),
+    )
+    .await
+    .map_err(|e| {
+        debug!("{e}");
+        e
+    })
+    .map(Submission::from)
+    .map(|v| (StatusCode::CREATED, Json(v)))
 }
 
 #[utoipa::path(
   patch,
   path = "/cities/submissions/{submission_id}",
-  description = "Update a city submission.",
+  description = "Update a city submission. The status of a submission is changed by approving or rejecting it.",
   tag = TAG,
   params(
     ("submission_id" = i32, Path, description = "Submission identifier", example = "1"),
@@ -329,14 +465,159 @@ async fn post_cities_submission(
     ErrorResponses,
   ))]
 async fn patch_cities_submission(
-    Query(submission_id): Query<i32>,
+    Path(submission_id): Path<i32>,
+    ctx: Context,
     Json(submission): Json<submission::SubmissionPatch>,
 ) -> Result<Json<Submission>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    patch_cities_submission_adaptor(db, submission_id, submission)
-        .await
-        .map(Submission::from)
-        .map(Json)
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "patch_cities_submission_adaptor",
+        patch_cities_submission_adaptor(db, submission_id, submission, &ctx),
+    )
+    .await
+    .map(|submission| redact(Submission::from(submission), &ctx))
+    .map(Json)
+}
+
+#[utoipa::path(
+  delete,
+  path = "/cities/submissions/{submission_id}",
+  description = "Delete a city submission and its review history.",
+  tag = TAG,
+  params(
+    ("submission_id" = i32, Path, description = "Submission identifier", example = "1"),
+  ),
+  responses(
+    (status = NO_CONTENT, description = "Deletes a city submission"),
+    ErrorResponses,
+  ))]
+async fn delete_cities_submission(
+    Path(submission_id): Path<i32>,
+    ctx: Context,
+) -> Result<StatusCode, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "delete_cities_submission_adaptor",
+        delete_cities_submission_adaptor(db, submission_id, &ctx),
+    )
+    .await
+    .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+  post,
+  path = "/cities/submissions/{submission_id}/approve",
+  description = "Approve a city submission. Approving a submission queues the analysis of the city and creates a pending BNA pipeline.",
+  tag = TAG,
+  params(
+    ("submission_id" = i32, Path, description = "Submission identifier", example = "1"),
+  ),
+  request_body = SubmissionReviewPost,
+  responses(
+    (status = OK, description = "Approves a city submission and returns it with its review history", body = Submission),
+    (status = CONFLICT, description = "The submission cannot be moved to this status", body = APIErrors),
+    ErrorResponses,
+  ))]
+async fn post_cities_submission_approve(
+    Path(submission_id): Path<i32>,
+    ctx: Context,
+    Json(review): Json<SubmissionReviewPost>,
+) -> Result<Json<Submission>, ExecutionError> {
+    review_cities_submission(submission_id, ApprovalStatus::Approved, review, ctx).await
+}
+
+#[utoipa::path(
+  post,
+  path = "/cities/submissions/{submission_id}/reject",
+  description = "Reject a city submission. A note explaining the decision is required.",
+  tag = TAG,
+  params(
+    ("submission_id" = i32, Path, description = "Submission identifier", example = "1"),
+  ),
+  request_body = SubmissionReviewPost,
+  responses(
+    (status = OK, description = "Rejects a city submission and returns it with its review history", body = Submission),
+    (status = CONFLICT, description = "The submission cannot be moved to this status", body = APIErrors),
+    ErrorResponses,
+  ))]
+async fn post_cities_submission_reject(
+    Path(submission_id): Path<i32>,
+    ctx: Context,
+    Json(review): Json<SubmissionReviewPost>,
+) -> Result<Json<Submission>, ExecutionError> {
+    review_cities_submission(submission_id, ApprovalStatus::Rejected, review, ctx).await
+}
+
+async fn review_cities_submission(
+    submission_id: i32,
+    status: ApprovalStatus,
+    review: SubmissionReviewPost,
+    ctx: Context,
+) -> Result<Json<Submission>, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    let queue = analysis_queue_or_init().await;
+    metrics::adaptor(
+        "post_cities_submission_review_adaptor",
+        post_cities_submission_review_adaptor(db, queue, submission_id, status, review.note, &ctx),
+    )
+    .await
+    .map(|(submission, reviews)| redact(Submission::with_reviews(submission, reviews), &ctx))
+    .map(Json)
+}
+
+#[utoipa::path(
+  post,
+  path = "/cities/submissions/personal-data/export",
+  description = "Export all the personal data held about a submitter. Restricted to the administrators.",
+  tag = TAG,
+  request_body = PersonalDataRequest,
+  responses(
+    (status = OK, description = "Exports the submissions made with an email address", body = PersonalDataExport),
+    ErrorResponses,
+  ))]
+async fn post_cities_submissions_export(
+    ctx: Context,
+    Json(request): Json<PersonalDataRequest>,
+) -> Result<Json<PersonalDataExport>, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    let submissions = metrics::adaptor(
+        "post_cities_submissions_export_adaptor",
+        post_cities_submissions_export_adaptor(db, &request.email, ctx),
+    )
+    .await?
+    .into_iter()
+    .map(|(submission, reviews)| Submission::with_reviews(submission, reviews))
+    .collect();
+    Ok(Json(PersonalDataExport {
+        email: request.email,
+        submissions,
+    }))
+}
+
+#[utoipa::path(
+  post,
+  path = "/cities/submissions/personal-data/erasure",
+  description = "Anonymize all the submissions of a submitter, keeping the city requests themselves. Restricted to the administrators.",
+  tag = TAG,
+  request_body = PersonalDataRequest,
+  responses(
+    (status = OK, description = "Anonymizes the submissions made with an email address", body = PersonalDataErasure),
+    ErrorResponses,
+  ))]
+async fn post_cities_submissions_erasure(
+    ctx: Context,
+    Json(request): Json<PersonalDataRequest>,
+) -> Result<Json<PersonalDataErasure>, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    let erased = metrics::adaptor(
+        "post_cities_submissions_erasure_adaptor",
+        post_cities_submissions_erasure_adaptor(db, &request.email, ctx),
+    )
+    .await?;
+    Ok(Json(PersonalDataErasure {
+        email: request.email,
+        erased,
+    }))
 }
 
 #[utoipa::path(
@@ -356,9 +637,13 @@ async fn get_top_cities(
     Path((year, count)): Path<(i32, u64)>,
     ctx: Context,
 ) -> Result<Json<CitiesWithSummary>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let count = count.clamp(1, TOP_CITIES_MAX_COUNT);
-    let models = get_top_cities_adaptor(db, year, count, ctx).await?;
+    let db = &database_read_connect_or_init().await?;
+    let count = count.clamp(1, config().pagination.top_cities_max_count);
+    let models = metrics::adaptor(
+        "get_top_cities_adaptor",
+        get_top_cities_adaptor(db, year, count, ctx),
+    )
+    .await?;
 
     let payload = models
         .iter()
@@ -371,6 +656,89 @@ async fn get_top_cities(
     Ok(Json(CitiesWithSummary(payload)))
 }
 
+/// Declares the analysis endpoint of a version of the API, the versions only differing
+/// by the schema of the pipeline and the prefix of its location.
+macro_rules! analysis_endpoint {
+    (version: $version:ident, pipeline: $pipeline:ident $(,)?) => {
+        #[utoipa::path(
+          post,
+          path = "/cities/{country}/{region}/{name}/analyses",
+          description = "Request a new BNA analysis for a specific city.",
+          tag = TAG,
+          params(
+            CityParams,
+          ),
+          request_body = AnalysisPost,
+          responses(
+            (status = ACCEPTED, description = "Queues a new analysis and returns its pipeline", body = $pipeline,
+              headers(
+                ("Location" = String, description = "Location of the pipeline performing the analysis")
+              )
+            ),
+            ErrorResponses,
+          ))]
+        pub(super) async fn post_cities_analysis(
+            Path(params): Path<CitiesPathParameters>,
+            ctx: Context,
+            Json(analysis): Json<AnalysisPost>,
+        ) -> Result<
+            (
+                StatusCode,
+                [(header::HeaderName, String); 1],
+                Json<$pipeline>,
+            ),
+            ExecutionError,
+        > {
+            let pipeline = queue_analysis(&params, analysis, ctx.clone()).await?;
+            let location = format!(
+                "{}/pipelines/bna/{}",
+                ApiVersion::$version.prefix(),
+                pipeline.state_machine_id
+            );
+            Ok((
+                StatusCode::ACCEPTED,
+                [(header::LOCATION, location)],
+                Json(pipeline_schema(pipeline, &ctx)?),
+            ))
+        }
+    };
+}
+
+mod v1 {
+    use super::*;
+
+    analysis_endpoint!(version: V1, pipeline: BnaPipeline);
+}
+
+mod v2 {
+    use super::*;
+
+    analysis_endpoint!(version: V2, pipeline: BnaPipelineV2);
+}
+
+/// Queues a new analysis of a city and returns its pipeline.
+async fn queue_analysis(
+    params: &CitiesPathParameters,
+    analysis: AnalysisPost,
+    ctx: Context,
+) -> Result<entity::bna_pipeline::Model, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    let queue = analysis_queue_or_init().await;
+    metrics::adaptor(
+        "post_cities_analysis_adaptor",
+        post_cities_analysis_adaptor(
+            db,
+            queue,
+            &params.country,
+            &params.region,
+            &params.name,
+            analysis.version,
+            ctx,
+        ),
+    )
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use axum::extract::Query;
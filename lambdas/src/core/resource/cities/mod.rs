@@ -4,6 +4,7 @@ mod db;
 pub mod endpoint;
 mod schema;
 
+use chrono::{DateTime, FixedOffset};
 use effortless::{api::parse_path_parameter, error::APIErrors};
 use serde::Deserialize;
 
@@ -58,6 +59,39 @@ pub fn extract_path_parameters(
     })
 }
 
+/// Filters applied to the submission listing.
+#[derive(Debug, Default, Clone)]
+pub struct SubmissionFilter {
+    /// Approval status.
+    pub status: Option<String>,
+    /// Country name, ignoring the case.
+    pub country: Option<String>,
+    /// Region name, ignoring the case.
+    pub region: Option<String>,
+    /// Submissions created at or after this date.
+    pub created_after: Option<DateTime<FixedOffset>>,
+    /// Submissions created at or before this date.
+    pub created_before: Option<DateTime<FixedOffset>>,
+    /// Email address of the submitter, ignoring the case.
+    pub email: Option<String>,
+    /// Free text matched against the city name, and against the personal data of the
+    /// submitter when `personal_data` is set.
+    pub q: Option<String>,
+    /// Whether the free text search includes the personal data.
+    pub personal_data: bool,
+}
+
+/// Normalizes a location name to compare it with other ones.
+///
+/// The comparison is case insensitive and ignores the extra whitespaces.
+pub(crate) fn normalize_location(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,4 +120,10 @@ mod tests {
         assert_eq!(r.region, region);
         assert_eq!(r.name, name);
     }
+
+    #[test]
+    fn test_normalize_location() {
+        assert_eq!(normalize_location("  Santa   Rosa "), "santa rosa");
+        assert_eq!(normalize_location("NEW MEXICO"), "new mexico");
+    }
 }
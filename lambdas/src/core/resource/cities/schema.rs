@@ -1,7 +1,7 @@
 //! Describes the Citi schemas.
 use crate::core::resource::schema::{City, Country};
 use chrono::DateTime;
-use entity::{submission, summary};
+use entity::{submission, submission_review, summary};
 use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
 use std::vec::Vec;
@@ -88,6 +88,8 @@ pub(crate) struct RatingSummary {
     /// specification with the YY.0M[.Minor] scheme.
     #[schema(examples("23.12"))]
     version: String,
+    /// Identifier of the pipeline which produced the analysis, if any
+    pipeline_id: Option<Uuid>,
 }
 
 /*
@@ -104,6 +106,7 @@ impl From<summary::Model> for RatingSummary {
             residential_speed_limit_override: value.residential_speed_limit_override,
             score: value.score,
             version: value.version,
+            pipeline_id: value.pipeline_id,
         }
     }
 }
@@ -155,6 +158,35 @@ pub(crate) struct Submission {
     status: String,
     /// Creation date
     created_at: DateTime<chrono::FixedOffset>,
+    /// Review history of the submission, oldest first. Only provided when
+    /// fetching a specific submission.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reviews: Option<Vec<SubmissionReview>>,
+}
+
+impl Submission {
+    /// Masks the personal data of the submitter.
+    pub(crate) fn redacted(self) -> Self {
+        Self {
+            first_name: mask(&self.first_name),
+            last_name: mask(&self.last_name),
+            occupation: self.occupation.as_deref().map(mask),
+            organization: self.organization.as_deref().map(mask),
+            email: mask_email(&self.email),
+            ..self
+        }
+    }
+
+    /// Creates a submission with its review history.
+    pub(crate) fn with_reviews(
+        value: submission::Model,
+        reviews: Vec<submission_review::Model>,
+    ) -> Self {
+        Self {
+            reviews: Some(reviews.into_iter().map(SubmissionReview::from).collect()),
+            ..Self::from(value)
+        }
+    }
 }
 
 impl From<submission::Model> for Submission {
@@ -173,6 +205,56 @@ impl From<submission::Model> for Submission {
             consent: value.consent,
             status: value.status,
             created_at: value.created_at,
+            reviews: None,
+        }
+    }
+}
+
+/// Masks a value, only keeping its first character.
+fn mask(value: &str) -> String {
+    match value.chars().next() {
+        Some(first) => format!("{first}***"),
+        None => String::new(),
+    }
+}
+
+/// Masks the local part of an email address, keeping the domain.
+fn mask_email(value: &str) -> String {
+    match value.split_once('@') {
+        Some((local, domain)) => format!("{}@{domain}", mask(local)),
+        None => mask(value),
+    }
+}
+
+#[derive(ToSchema, Serialize)]
+pub(crate) struct SubmissionReview {
+    /// Review identifier
+    id: i32,
+    /// Identity of the reviewer
+    #[schema(examples("jane.doe@peopleforbikes.org"))]
+    reviewer: String,
+    /// Status of the submission before the review
+    #[schema(examples("Pending"))]
+    previous_status: String,
+    /// Status of the submission after the review
+    #[schema(examples("Rejected"))]
+    status: String,
+    /// Reviewer note
+    #[schema(examples("The city is already part of the dataset."))]
+    note: Option<String>,
+    /// Review date
+    created_at: DateTime<chrono::FixedOffset>,
+}
+
+impl From<submission_review::Model> for SubmissionReview {
+    fn from(value: submission_review::Model) -> Self {
+        Self {
+            id: value.id,
+            reviewer: value.reviewer,
+            previous_status: value.previous_status,
+            status: value.status,
+            note: value.note,
+            created_at: value.created_at,
         }
     }
 }
@@ -206,7 +288,8 @@ pub(crate) struct SubmissionPost {
     /// this concept, then the country name is used.
     #[schema(examples("Antwerp"))]
     region: Option<String>,
-    /// Numerical city identifier given by the U.S. census, or 0 for non-US cities
+    /// Numerical city identifier given by the U.S. census, or 0 for non-US cities.
+    /// The 7-digit census place code is required for the US cities.
     #[schema(examples("4805000"))]
     fips_code: String,
     /// Consent status
@@ -246,13 +329,15 @@ pub(crate) struct SubmissionPatch {
     /// this concept, then the country name is used.
     #[schema(examples("Antwerp"))]
     region: Option<String>,
-    /// Numerical city identifier given by the U.S. census, or 0 for non-US cities
+    /// Numerical city identifier given by the U.S. census, or 0 for non-US cities.
+    /// The 7-digit census place code is required for the US cities.
     #[schema(examples("4805000"))]
     fips_code: String,
     /// Consent status
     #[schema(examples("true"))]
     consent: bool,
-    /// Submission status, e.g. "Pending"
+    /// Submission status. It cannot be changed directly, use the approve and reject
+    /// actions instead.
     #[schema(examples("Pending"))]
     status: String,
 }
@@ -285,3 +370,82 @@ pub(crate) struct CityWithSummary {
 
 #[derive(ToSchema, Serialize)]
 pub(crate) struct CitiesWithSummary(pub(crate) Vec<CityWithSummary>);
+
+#[derive(ToSchema, Deserialize)]
+pub(crate) struct AnalysisPost {
+    /// Version of the analyzer to use. The latest version is used if not
+    /// specified.
+    #[schema(examples("25.06"))]
+    pub(crate) version: Option<String>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub(crate) struct SubmissionReviewPost {
+    /// Reviewer note explaining the decision. Required to reject a submission.
+    #[schema(examples("The city is already part of the dataset."))]
+    pub(crate) note: Option<String>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub(crate) struct PersonalDataRequest {
+    /// Email address of the person whose data is requested
+    #[schema(examples("jane.doe@orgllc.com"))]
+    pub(crate) email: String,
+}
+
+#[derive(ToSchema, Serialize)]
+pub(crate) struct PersonalDataExport {
+    /// Email address of the person
+    #[schema(examples("jane.doe@orgllc.com"))]
+    pub(crate) email: String,
+    /// Submissions made with this email address, with their review history
+    pub(crate) submissions: Vec<Submission>,
+}
+
+#[derive(ToSchema, Serialize)]
+pub(crate) struct PersonalDataErasure {
+    /// Email address of the person
+    #[schema(examples("jane.doe@orgllc.com"))]
+    pub(crate) email: String,
+    /// Number of submissions which were anonymized
+    #[schema(examples(2))]
+    pub(crate) erased: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("Jane"), "J***");
+        assert_eq!(mask(""), "");
+        assert_eq!(mask_email("jane.doe@orgllc.com"), "j***@orgllc.com");
+        assert_eq!(mask_email("jane.doe"), "j***");
+    }
+
+    #[test]
+    fn test_redacted_submission() {
+        let submission = Submission::from(submission::Model {
+            id: 1,
+            first_name: String::from("Jane"),
+            last_name: String::from("Doe"),
+            occupation: Some(String::from("CTO")),
+            organization: Some(String::from("Organization LLC")),
+            email: String::from("jane.doe@orgllc.com"),
+            country: String::from("Belgium"),
+            city: String::from("Antwerp"),
+            region: Some(String::from("Antwerp")),
+            fips_code: String::from("0"),
+            consent: true,
+            status: String::from("Pending"),
+            created_at: DateTime::default(),
+        })
+        .redacted();
+        assert_eq!(submission.first_name, "J***");
+        assert_eq!(submission.occupation.as_deref(), Some("C***"));
+        assert_eq!(submission.organization.as_deref(), Some("O***"));
+        assert_eq!(submission.email, "j***@orgllc.com");
+        assert_eq!(submission.city, "Antwerp");
+    }
+}
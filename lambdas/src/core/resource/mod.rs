@@ -6,3 +6,4 @@ pub mod reports;
 pub mod schema;
 pub mod system;
 pub mod usstates;
+pub mod webhooks;
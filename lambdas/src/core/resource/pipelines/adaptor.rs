@@ -1,10 +1,31 @@
-use super::db::{fetch_bna_pipeline, fetch_bna_pipelines};
-use crate::{Context, ExecutionError, PageFlow, Paginatron};
+use super::{
+    db::{
+        fetch_bna_pipeline, fetch_bna_pipeline_for_update, fetch_bna_pipeline_rating,
+        fetch_bna_pipelines, fetch_stale_bna_pipelines, update_stale_bna_pipeline,
+    },
+    StaleThresholds,
+};
+use crate::{
+    core::{
+        config::config,
+        resource::ratings::{adaptor::get_rating_adaptor, Bna},
+        webhook::record_event,
+    },
+    Context, ExecutionError, PageFlow, Paginatron,
+};
+use chrono::{FixedOffset, Utc};
 use entity::{
     bna_pipeline,
-    wrappers::bna_pipeline::{BNAPipelinePatch, BNAPipelinePost},
+    wrappers::{
+        bna_pipeline::{BNAPipelinePatch, BNAPipelinePost},
+        BNAPipelineStatus, WebhookEvent,
+    },
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, DatabaseConnection, IntoActiveModel, TransactionTrait,
 };
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, IntoActiveModel};
+use serde_json::json;
+use std::str::FromStr;
 use tracing::info;
 use uuid::Uuid;
 
@@ -25,13 +46,40 @@ pub async fn get_pipelines_bna_adaptor(
     }
 }
 
+pub(crate) async fn get_pipelines_bna_rating_adaptor(
+    db: &DatabaseConnection,
+    pipeline_id: Uuid,
+    ctx: Context,
+) -> Result<Bna, ExecutionError> {
+    // Fetch the pipeline and its rating.
+    let model = fetch_bna_pipeline_rating(db, pipeline_id).await?;
+    match model {
+        Some((_, Some(summary))) => get_rating_adaptor(db, summary.id, ctx).await,
+        Some((_, None)) => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("the pipeline with the ID {pipeline_id} did not produce a rating"),
+        )),
+        None => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find a pipeline with the ID {pipeline_id}"),
+        )),
+    }
+}
+
 pub async fn get_pipelines_bnas_adaptor(
     db: &DatabaseConnection,
+    stale: bool,
     page: u64,
     page_size: u64,
 ) -> Result<PageFlow<Vec<bna_pipeline::Model>>, ExecutionError> {
-    // Fetch a page of summary.
-    let (total_items, models) = fetch_bna_pipelines(db, page, page_size).await?;
+    // Prepare the stale filter.
+    let thresholds = StaleThresholds::from(&config().pipelines);
+    let stale = stale.then(|| (&thresholds, now()));
+
+    // Fetch a page of pipelines.
+    let (total_items, models) = fetch_bna_pipelines(db, stale, page, page_size).await?;
 
     // Return the paginated response.
     Ok(PageFlow::new(
@@ -61,11 +109,181 @@ pub async fn patch_pipelines_bna_adaptor(
     bna_pipeline: BNAPipelinePatch,
     analysis_id: Uuid,
 ) -> Result<entity::bna_pipeline::Model, ExecutionError> {
+    // Check whether the pipeline is reaching a final status. The pipeline stays locked
+    // until it is updated, so that concurrent updates notify the subscribers only once.
+    let txn = db.begin().await?;
+    let previous_status = fetch_bna_pipeline_for_update(&txn, analysis_id)
+        .await?
+        .map(|m| m.status);
+    let event = bna_pipeline
+        .status
+        .as_deref()
+        .filter(|status| previous_status.as_deref() != Some(*status))
+        .and_then(pipeline_event);
+
     // Turn the patch model into an active model.
     let mut active_model = bna_pipeline.into_active_model();
     active_model.state_machine_id = ActiveValue::Unchanged(analysis_id);
 
-    // Update the entry.
-    let model = active_model.update(db).await?;
+    // Update the entry and notify the subscribers.
+    let model = active_model.update(&txn).await?;
+    if let Some(event) = event {
+        record_event(&txn, event, json!(model)).await?;
+    }
+    txn.commit().await?;
     Ok(model)
 }
+
+/// Returns the event to notify when a pipeline reaches a final status.
+fn pipeline_event(status: &str) -> Option<WebhookEvent> {
+    match BNAPipelineStatus::from_str(status).ok()? {
+        BNAPipelineStatus::Completed => Some(WebhookEvent::PipelineCompleted),
+        BNAPipelineStatus::Failed | BNAPipelineStatus::TimedOut => {
+            Some(WebhookEvent::PipelineFailed)
+        }
+        BNAPipelineStatus::Pending | BNAPipelineStatus::Processing => None,
+    }
+}
+
+/// Marks the pipelines which stopped progressing as failed or timed out.
+///
+/// Returns the pipelines which were updated. A pipeline updated since it was found
+/// stale, either by another sweep or by the analyzer, is left as is and no event is
+/// notified for it.
+pub async fn sweep_stale_pipelines_adaptor(
+    db: &DatabaseConnection,
+    thresholds: &StaleThresholds,
+) -> Result<Vec<bna_pipeline::Model>, ExecutionError> {
+    let now = now();
+    let stale = fetch_stale_bna_pipelines(db, thresholds, now).await?;
+
+    let mut swept = Vec::with_capacity(stale.len());
+    for model in stale {
+        let active_model = sweep_pipeline(model.clone(), thresholds, now);
+        let txn = db.begin().await?;
+        let Some(model) = update_stale_bna_pipeline(&txn, &model, active_model).await? else {
+            info!(
+                "Brokenspoke pipeline {} changed in the meantime, skipping it",
+                model.state_machine_id
+            );
+            txn.rollback().await?;
+            continue;
+        };
+        record_event(&txn, WebhookEvent::PipelineFailed, json!(model)).await?;
+        txn.commit().await?;
+        swept.push(model);
+    }
+
+    Ok(swept)
+}
+
+/// Prepares the update of a pipeline which stopped progressing.
+fn sweep_pipeline(
+    model: bna_pipeline::Model,
+    thresholds: &StaleThresholds,
+    now: chrono::DateTime<FixedOffset>,
+) -> bna_pipeline::ActiveModel {
+    let minutes = thresholds.for_step(&model.step).num_minutes();
+    let status = stale_status(&model.status);
+    let reason = match status {
+        BNAPipelineStatus::Failed => format!(
+            "never started, still pending on step {} after {minutes} minutes",
+            model.step
+        ),
+        _ => format!(
+            "no progress on step {} for more than {minutes} minutes",
+            model.step
+        ),
+    };
+    info!(
+        "marking Brokenspoke pipeline {} as {status}: {reason}",
+        model.state_machine_id
+    );
+    let mut active_model = model.into_active_model();
+    active_model.status = ActiveValue::Set(status.to_string());
+    active_model.status_reason = ActiveValue::Set(Some(reason));
+    active_model.updated_at = ActiveValue::Set(Some(now));
+    active_model
+}
+
+/// Returns the status of a pipeline which stopped progressing.
+///
+/// A pipeline still pending was never picked up by the analyzer, therefore it failed,
+/// whereas a pipeline which stopped while processing timed out.
+fn stale_status(status: &str) -> BNAPipelineStatus {
+    match BNAPipelineStatus::from_str(status) {
+        Ok(BNAPipelineStatus::Pending) => BNAPipelineStatus::Failed,
+        _ => BNAPipelineStatus::TimedOut,
+    }
+}
+
+/// Returns the current time as stored in the database.
+fn now() -> chrono::DateTime<FixedOffset> {
+    Utc::now().with_timezone(&FixedOffset::east_opt(0).expect("a valid offset"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn pipeline(status: BNAPipelineStatus) -> bna_pipeline::Model {
+        bna_pipeline::Model {
+            state_machine_id: Uuid::new_v4(),
+            step: String::from("Analysis"),
+            sqs_message: None,
+            fargate_price_id: None,
+            fargate_task_arn: None,
+            s3_bucket: None,
+            status: status.to_string(),
+            start_time: now() - Duration::days(2),
+            end_time: None,
+            cost: None,
+            status_reason: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_sweep_pipeline_times_out_processing_pipelines() {
+        let now = now();
+        let model = pipeline(BNAPipelineStatus::Processing);
+        let swept = sweep_pipeline(model.clone(), &StaleThresholds::default(), now);
+        assert_eq!(
+            swept.state_machine_id,
+            ActiveValue::Unchanged(model.state_machine_id)
+        );
+        assert_eq!(
+            swept.status,
+            ActiveValue::Set(BNAPipelineStatus::TimedOut.to_string())
+        );
+        assert_eq!(
+            swept.status_reason,
+            ActiveValue::Set(Some(String::from(
+                "no progress on step Analysis for more than 720 minutes"
+            )))
+        );
+        assert_eq!(swept.updated_at, ActiveValue::Set(Some(now)));
+    }
+
+    #[test]
+    fn test_sweep_pipeline_fails_pending_pipelines() {
+        let now = now();
+        let swept = sweep_pipeline(
+            pipeline(BNAPipelineStatus::Pending),
+            &StaleThresholds::default(),
+            now,
+        );
+        assert_eq!(
+            swept.status,
+            ActiveValue::Set(BNAPipelineStatus::Failed.to_string())
+        );
+        assert_eq!(
+            swept.status_reason,
+            ActiveValue::Set(Some(String::from(
+                "never started, still pending on step Analysis after 720 minutes"
+            )))
+        );
+        assert_eq!(swept.updated_at, ActiveValue::Set(Some(now)));
+    }
+}
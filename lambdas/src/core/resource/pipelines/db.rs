@@ -1,5 +1,14 @@
-use entity::bna_pipeline;
-use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use super::StaleThresholds;
+use entity::{
+    bna_pipeline, summary,
+    wrappers::{BNAPipelineStatus, BNAPipelineStep},
+};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, Func},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
 use uuid::Uuid;
 
 pub async fn fetch_bna_pipeline(
@@ -9,12 +18,47 @@ pub async fn fetch_bna_pipeline(
     bna_pipeline::Entity::find_by_id(pipeline_id).one(db).await
 }
 
+/// Fetches a pipeline and locks it until the end of the transaction.
+pub async fn fetch_bna_pipeline_for_update(
+    db: &impl ConnectionTrait,
+    pipeline_id: Uuid,
+) -> Result<Option<bna_pipeline::Model>, DbErr> {
+    bna_pipeline::Entity::find_by_id(pipeline_id)
+        .lock_exclusive()
+        .one(db)
+        .await
+}
+
+pub async fn fetch_bna_pipeline_rating(
+    db: &DatabaseConnection,
+    pipeline_id: Uuid,
+) -> Result<Option<(bna_pipeline::Model, Option<summary::Model>)>, sea_orm::DbErr> {
+    // A pipeline produces a single rating, but prefer the most recent one if it was
+    // ever saved more than once.
+    bna_pipeline::Entity::find_by_id(pipeline_id)
+        .find_also_related(summary::Entity)
+        .order_by_desc(summary::Column::CreatedAt)
+        .one(db)
+        .await
+}
+
 pub async fn fetch_bna_pipelines(
     db: &DatabaseConnection,
+    stale: Option<(&StaleThresholds, DateTimeWithTimeZone)>,
     page: u64,
     page_size: u64,
 ) -> Result<(u64, Vec<bna_pipeline::Model>), sea_orm::DbErr> {
-    let select = bna_pipeline::Entity::find();
+    // Filter the query if needed.
+    let mut conditions = Condition::all();
+    if let Some((thresholds, now)) = stale {
+        conditions = conditions.add(
+            Condition::any()
+                .add(stale_condition(thresholds, now))
+                .add(bna_pipeline::Column::Status.eq(BNAPipelineStatus::TimedOut.to_string())),
+        );
+    }
+
+    let select = bna_pipeline::Entity::find().filter(conditions);
     let models = select
         .clone()
         .paginate(db, page_size)
@@ -23,3 +67,79 @@ pub async fn fetch_bna_pipelines(
     let count = select.count(db).await?;
     Ok((count, models))
 }
+
+/// Fetches all the pipelines stuck on their current step.
+pub async fn fetch_stale_bna_pipelines(
+    db: &DatabaseConnection,
+    thresholds: &StaleThresholds,
+    now: DateTimeWithTimeZone,
+) -> Result<Vec<bna_pipeline::Model>, sea_orm::DbErr> {
+    bna_pipeline::Entity::find()
+        .filter(stale_condition(thresholds, now))
+        .all(db)
+        .await
+}
+
+/// Updates a pipeline which stopped progressing, unless it changed since it was fetched.
+///
+/// Another sweep, or the analyzer reporting a progress, may update the pipeline in the
+/// meantime, therefore the update only applies to a pipeline still in flight and
+/// untouched since `stale` was fetched. Returns `None` if it no longer applies.
+pub async fn update_stale_bna_pipeline(
+    db: &impl ConnectionTrait,
+    stale: &bna_pipeline::Model,
+    active_model: bna_pipeline::ActiveModel,
+) -> Result<Option<bna_pipeline::Model>, DbErr> {
+    let untouched = match stale.updated_at {
+        Some(updated_at) => bna_pipeline::Column::UpdatedAt.eq(updated_at),
+        None => bna_pipeline::Column::UpdatedAt.is_null(),
+    };
+    let models = bna_pipeline::Entity::update_many()
+        .set(active_model)
+        .filter(bna_pipeline::Column::StateMachineId.eq(stale.state_machine_id))
+        .filter(bna_pipeline::Column::Status.is_in(in_flight_statuses()))
+        .filter(untouched)
+        .exec_with_returning(db)
+        .await?;
+    Ok(models.into_iter().next())
+}
+
+/// Returns the statuses of the pipelines which are still in flight.
+fn in_flight_statuses() -> [String; 2] {
+    [
+        BNAPipelineStatus::Pending.to_string(),
+        BNAPipelineStatus::Processing.to_string(),
+    ]
+}
+
+/// Builds the condition matching the in-flight pipelines which were not updated
+/// during the threshold of their current step.
+///
+/// The last activity of a pipeline is its last update, or its start time if it was
+/// never updated.
+fn stale_condition(thresholds: &StaleThresholds, now: DateTimeWithTimeZone) -> Condition {
+    let last_activity = Func::coalesce([
+        Expr::col(bna_pipeline::Column::UpdatedAt).into(),
+        Expr::col(bna_pipeline::Column::StartTime).into(),
+    ]);
+    let steps = [
+        BNAPipelineStep::Setup,
+        BNAPipelineStep::Analysis,
+        BNAPipelineStep::Save,
+        BNAPipelineStep::Cleanup,
+    ]
+    .into_iter()
+    .fold(Condition::any(), |condition, step| {
+        let step = step.to_string();
+        let cutoff = now - thresholds.for_step(&step);
+        condition.add(
+            Condition::all()
+                .add(bna_pipeline::Column::Step.eq(step))
+                .add(Expr::expr(last_activity.clone()).lt(cutoff)),
+        )
+    });
+
+    Condition::all()
+        .add(bna_pipeline::Column::Status.is_in(in_flight_statuses()))
+        .add(steps)
+}
@@ -1,19 +1,25 @@
 use super::adaptor::{
-    get_pipelines_bna_adaptor, get_pipelines_bnas_adaptor, patch_pipelines_bna_adaptor,
-    post_pipelines_bna_adaptor,
+    get_pipelines_bna_adaptor, get_pipelines_bna_rating_adaptor, get_pipelines_bnas_adaptor,
+    patch_pipelines_bna_adaptor, post_pipelines_bna_adaptor,
 };
-use super::schema::{BnaPipeline, BnaPipelinePatch, BnaPipelinePost, BnaPipelines};
-use crate::database_connect_or_init;
-use crate::{
-    core::resource::schema::{ErrorResponses, PaginationParameters},
-    Context, ExecutionError,
+use super::schema::{
+    pipeline_schema, BnaPipeline, BnaPipelinePatch, BnaPipelinePatchV2, BnaPipelinePost,
+    BnaPipelineV2, BnaPipelines, BnaPipelinesV2,
 };
-use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    Json,
+use crate::{
+    core::{
+        extract::{Json, Path, Query},
+        metrics,
+        resource::{
+            ratings::schema::{Rating, RatingV2},
+            schema::{ErrorResponses, PaginationParameters},
+        },
+    },
+    database_connect_or_init, database_read_connect_or_init, Context, ExecutionError, PageFlow,
 };
+use axum::http::StatusCode;
 use entity::wrappers::bna_pipeline::{BNAPipelinePatch, BNAPipelinePost};
+use serde::Deserialize;
 use serde_json::{json, Value};
 use tracing::debug;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -22,105 +28,236 @@ use uuid::Uuid;
 const TAG: &str = "pipeline";
 
 pub fn routes() -> OpenApiRouter {
-    OpenApiRouter::new()
-        .routes(routes!(get_pipelines_bna))
-        .routes(routes!(get_pipelines_bnas))
-        .routes(routes!(post_pipelines_bna))
-        .routes(routes!(patch_pipelines_bna))
+    v1::routes()
 }
 
-#[utoipa::path(
-  get,
-  path = "/pipelines/bna/{pipeline_id}",
-  description = "Get the details of a specific BNA pipeline",
-  tag = TAG,
-  params(
-    ("pipeline_id" = Uuid, Path, description = "Pipeline identifier")
-  ),
-  responses(
-    (status = OK, description = "Fetches the details of a BNA pipeline", body = BnaPipeline),
-    ErrorResponses,
-  ))]
-async fn get_pipelines_bna(
-    Path(pipeline_id): Path<Uuid>,
-    ctx: Context,
-) -> Result<Json<BnaPipeline>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_pipelines_bna_adaptor(db, pipeline_id, ctx)
-        .await
-        .map(BnaPipeline::from)
-        .map(Json)
+pub fn routes_v2() -> OpenApiRouter {
+    v2::routes()
 }
 
-#[utoipa::path(
-  get,
-  path = "/pipelines/bna",
-  description = "Get the details of all BNA pipelines",
-  tag = TAG,
-  params(
-    PaginationParameters,
-  ),
-  responses(
-    (status = OK, description = "Fetches the details of all BNA pipelines", body = BnaPipelines),
-    ErrorResponses,
-  ))]
-async fn get_pipelines_bnas(
-    Query(pagination): Query<PaginationParameters>,
-) -> Result<Json<Value>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_pipelines_bnas_adaptor(db, pagination.page(), pagination.page_size())
-        .await
-        .map(|v| Json(json!(v.payload())))
+#[derive(Deserialize)]
+struct PipelineParameters {
+    pub stale: Option<bool>,
 }
 
-#[utoipa::path(
-  post,
-  path = "/pipelines/bna",
-  description = "Create a new BNA pipeline",
-  tag = TAG,
-  request_body = BnaPipelinePost,
-  responses(
-    (status = CREATED, description = "Creates a new city submission", body = BnaPipeline),
-    ErrorResponses,
-  ))]
-async fn post_pipelines_bna(
-    Json(bna_pipeline): Json<BNAPipelinePost>,
-) -> Result<(StatusCode, Json<BnaPipeline>), ExecutionError> {
-    let db = database_connect_or_init().await?;
-    post_pipelines_bna_adaptor(db, bna_pipeline)
-        .await
-        .map_err(|e| {
-            debug!("{e}");
-            e
-        })
-        .map(BnaPipeline::from)
-        .map(|v| (StatusCode::CREATED, Json(v)))
+/// Response listing the pipelines.
+trait PipelinesResponse: Sized {
+    fn from_page(
+        page: PageFlow<Vec<entity::bna_pipeline::Model>>,
+        ctx: &Context,
+    ) -> Result<Self, ExecutionError>;
 }
 
-#[utoipa::path(
-  patch,
-  path = "/pipelines/bna/{pipeline_id}",
-  description = "Update the details of a specific BNA pipeline",
-  tag = TAG,
-  request_body = BnaPipelinePatch,
-  params(
-    ("pipeline_id" = Uuid, Path, description = "Pipeline identifier")
-  ),
-  responses(
-    (status = OK, description = "Updates the details of a BNA pipeline", body = BnaPipeline),
-    ErrorResponses,
-  ))]
-async fn patch_pipelines_bna(
-    Path(analysis_id): Path<Uuid>,
-    Json(bna_pipeline): Json<BNAPipelinePatch>,
-) -> Result<Json<BnaPipeline>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    patch_pipelines_bna_adaptor(db, bna_pipeline, analysis_id)
-        .await
-        .map_err(|e| {
-            debug!("{e}");
-            e
-        })
-        .map(BnaPipeline::from)
-        .map(Json)
+/// The version 1 returns the stored pipelines as is, without the pagination headers.
+impl PipelinesResponse for Json<Value> {
+    fn from_page(
+        page: PageFlow<Vec<entity::bna_pipeline::Model>>,
+        _ctx: &Context,
+    ) -> Result<Self, ExecutionError> {
+        Ok(Json(json!(page.payload())))
+    }
+}
+
+impl PipelinesResponse for PageFlow<BnaPipelinesV2> {
+    fn from_page(
+        page: PageFlow<Vec<entity::bna_pipeline::Model>>,
+        ctx: &Context,
+    ) -> Result<Self, ExecutionError> {
+        page.try_map(|models| pipeline_schema(models, ctx))
+    }
+}
+
+/// Declares the pipeline endpoints of a version of the API.
+///
+/// The versions share the adaptors and only differ by the schemas the results are
+/// converted to, so every version expands the same handlers in its own module.
+macro_rules! pipeline_endpoints {
+    (
+        pipeline: $pipeline:ident,
+        pipelines: $pipelines:ident,
+        pipelines_response: $pipelines_response:ty,
+        pipeline_patch: $pipeline_patch:ident,
+        pipeline_patch_body: $pipeline_patch_body:ty,
+        rating: $rating:ident $(,)?
+    ) => {
+        pub(super) fn routes() -> OpenApiRouter {
+            OpenApiRouter::new()
+                .routes(routes!(get_pipelines_bna))
+                .routes(routes!(get_pipelines_bna_rating))
+                .routes(routes!(get_pipelines_bnas))
+                .routes(routes!(post_pipelines_bna))
+                .routes(routes!(patch_pipelines_bna))
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/pipelines/bna/{pipeline_id}",
+          description = "Get the details of a specific BNA pipeline",
+          tag = TAG,
+          params(
+            ("pipeline_id" = Uuid, Path, description = "Pipeline identifier")
+          ),
+          responses(
+            (status = OK, description = "Fetches the details of a BNA pipeline", body = $pipeline),
+            ErrorResponses,
+          ))]
+        async fn get_pipelines_bna(
+            Path(pipeline_id): Path<Uuid>,
+            ctx: Context,
+        ) -> Result<Json<$pipeline>, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            let model = metrics::adaptor(
+                "get_pipelines_bna_adaptor",
+                get_pipelines_bna_adaptor(db, pipeline_id, ctx.clone()),
+            )
+            .await?;
+            pipeline_schema(model, &ctx).map(Json)
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/pipelines/bna/{pipeline_id}/rating",
+          description = "Get the city rating produced by a specific BNA pipeline",
+          tag = TAG,
+          params(
+            ("pipeline_id" = Uuid, Path, description = "Pipeline identifier")
+          ),
+          responses(
+            (status = OK, description = "Fetches the city rating produced by a BNA pipeline", body = $rating),
+            ErrorResponses,
+          ))]
+        async fn get_pipelines_bna_rating(
+            Path(pipeline_id): Path<Uuid>,
+            ctx: Context,
+        ) -> Result<Json<$rating>, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            metrics::adaptor(
+                "get_pipelines_bna_rating_adaptor",
+                get_pipelines_bna_rating_adaptor(db, pipeline_id, ctx),
+            )
+            .await
+            .map($rating::from)
+            .map(Json)
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/pipelines/bna",
+          description = "Get the details of all BNA pipelines",
+          tag = TAG,
+          params(
+            ("stale" = Option<bool>, Query, description = "Only return the pipelines which stopped progressing or timed out", example = true),
+            PaginationParameters,
+          ),
+          responses(
+            (status = OK, description = "Fetches the details of all BNA pipelines", body = $pipelines),
+            ErrorResponses,
+          ))]
+        async fn get_pipelines_bnas(
+            Query(pipeline_params): Query<PipelineParameters>,
+            Query(pagination): Query<PaginationParameters>,
+            ctx: Context,
+        ) -> Result<$pipelines_response, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            let page = metrics::adaptor(
+                "get_pipelines_bnas_adaptor",
+                get_pipelines_bnas_adaptor(
+                    db,
+                    pipeline_params.stale.unwrap_or_default(),
+                    pagination.page(),
+                    pagination.page_size(),
+                ),
+            )
+            .await?;
+            <$pipelines_response>::from_page(page, &ctx)
+        }
+
+        #[utoipa::path(
+          post,
+          path = "/pipelines/bna",
+          description = "Create a new BNA pipeline",
+          tag = TAG,
+          request_body = BnaPipelinePost,
+          responses(
+            (status = CREATED, description = "Creates a new BNA pipeline", body = $pipeline),
+            ErrorResponses,
+          ))]
+        async fn post_pipelines_bna(
+            ctx: Context,
+            Json(bna_pipeline): Json<BNAPipelinePost>,
+        ) -> Result<(StatusCode, Json<$pipeline>), ExecutionError> {
+            let db = &database_connect_or_init().await?;
+            let model = metrics::adaptor(
+                "post_pipelines_bna_adaptor",
+                post_pipelines_bna_adaptor(db, bna_pipeline),
+            )
+            .await
+            .map_err(|e| {
+                debug!("{e}");
+                e
+            })?;
+            pipeline_schema(model, &ctx).map(|v| (StatusCode::CREATED, Json(v)))
+        }
+
+        #[utoipa::path(
+          patch,
+          path = "/pipelines/bna/{pipeline_id}",
+          description = "Update the details of a specific BNA pipeline",
+          tag = TAG,
+          request_body = $pipeline_patch,
+          params(
+            ("pipeline_id" = Uuid, Path, description = "Pipeline identifier")
+          ),
+          responses(
+            (status = OK, description = "Updates the details of a BNA pipeline", body = $pipeline),
+            ErrorResponses,
+          ))]
+        async fn patch_pipelines_bna(
+            Path(analysis_id): Path<Uuid>,
+            ctx: Context,
+            Json(bna_pipeline): Json<$pipeline_patch_body>,
+        ) -> Result<Json<$pipeline>, ExecutionError> {
+            let db = &database_connect_or_init().await?;
+            let model = metrics::adaptor(
+                "patch_pipelines_bna_adaptor",
+                patch_pipelines_bna_adaptor(
+                    db,
+                    BNAPipelinePatch::from(bna_pipeline),
+                    analysis_id,
+                ),
+            )
+            .await
+            .map_err(|e| {
+                debug!("{e}");
+                e
+            })?;
+            pipeline_schema(model, &ctx).map(Json)
+        }
+    };
+}
+
+mod v1 {
+    use super::*;
+
+    pipeline_endpoints!(
+        pipeline: BnaPipeline,
+        pipelines: BnaPipelines,
+        pipelines_response: Json<Value>,
+        pipeline_patch: BnaPipelinePatch,
+        pipeline_patch_body: BNAPipelinePatch,
+        rating: Rating,
+    );
+}
+
+mod v2 {
+    use super::*;
+
+    pipeline_endpoints!(
+        pipeline: BnaPipelineV2,
+        pipelines: BnaPipelinesV2,
+        pipelines_response: PageFlow<BnaPipelinesV2>,
+        pipeline_patch: BnaPipelinePatchV2,
+        pipeline_patch_body: BnaPipelinePatchV2,
+        rating: RatingV2,
+    );
 }
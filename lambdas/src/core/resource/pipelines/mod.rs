@@ -2,4 +2,101 @@
 pub mod adaptor;
 mod db;
 pub mod endpoint;
-mod schema;
+pub(crate) mod schema;
+
+use crate::core::config::PipelinesConfig;
+use chrono::Duration;
+use entity::wrappers::BNAPipelineStep;
+use std::str::FromStr;
+
+/// Maximum amount of time a pipeline may spend on a step without any update before
+/// being considered stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleThresholds {
+    /// Threshold for the `Setup` step.
+    pub setup: Duration,
+    /// Threshold for the `Analysis` step.
+    pub analysis: Duration,
+    /// Threshold for the `Save` step.
+    pub save: Duration,
+    /// Threshold for the `Cleanup` step.
+    pub cleanup: Duration,
+}
+
+impl Default for StaleThresholds {
+    fn default() -> Self {
+        Self::from(&PipelinesConfig::default())
+    }
+}
+
+impl From<&PipelinesConfig> for StaleThresholds {
+    fn from(value: &PipelinesConfig) -> Self {
+        let minutes = |value: u64| Duration::minutes(value as i64);
+        Self {
+            setup: minutes(value.stale_setup),
+            analysis: minutes(value.stale_analysis),
+            save: minutes(value.stale_save),
+            cleanup: minutes(value.stale_cleanup),
+        }
+    }
+}
+
+impl StaleThresholds {
+    /// Returns the threshold applying to a pipeline step.
+    ///
+    /// Unknown steps get the most permissive threshold.
+    pub fn for_step(&self, step: &str) -> Duration {
+        match BNAPipelineStep::from_str(step) {
+            Ok(BNAPipelineStep::Setup) => self.setup,
+            Ok(BNAPipelineStep::Analysis) => self.analysis,
+            Ok(BNAPipelineStep::Save) => self.save,
+            Ok(BNAPipelineStep::Cleanup) => self.cleanup,
+            Err(_) => self.max(),
+        }
+    }
+
+    /// Returns the longest threshold.
+    fn max(&self) -> Duration {
+        *[self.setup, self.analysis, self.save, self.cleanup]
+            .iter()
+            .max()
+            .expect("there is always at least one threshold")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_thresholds_for_step() {
+        let thresholds = StaleThresholds {
+            setup: Duration::minutes(10),
+            analysis: Duration::minutes(600),
+            save: Duration::minutes(20),
+            cleanup: Duration::minutes(30),
+        };
+        assert_eq!(thresholds.for_step("Setup"), Duration::minutes(10));
+        assert_eq!(thresholds.for_step("Analysis"), Duration::minutes(600));
+        assert_eq!(thresholds.for_step("Save"), Duration::minutes(20));
+        assert_eq!(thresholds.for_step("Cleanup"), Duration::minutes(30));
+        assert_eq!(thresholds.for_step("SqsMessage"), Duration::minutes(600));
+    }
+
+    #[test]
+    fn test_stale_thresholds_from_config() {
+        let thresholds = StaleThresholds::from(&PipelinesConfig {
+            stale_analysis: 90,
+            ..PipelinesConfig::default()
+        });
+        assert_eq!(
+            thresholds,
+            StaleThresholds {
+                setup: Duration::hours(1),
+                analysis: Duration::minutes(90),
+                save: Duration::hours(1),
+                cleanup: Duration::hours(1),
+            }
+        );
+    }
+}
@@ -1,7 +1,9 @@
 //! Describes the Pipeline schemas.
 use std::{fmt::Display, str::FromStr};
 
+use crate::{Context, ExecutionError};
 use chrono::DateTime;
+use entity::wrappers::bna_pipeline::BNAPipelinePatch;
 use sea_orm::prelude::Decimal;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -10,8 +12,10 @@ use uuid::Uuid;
 #[derive(ToSchema, Serialize, Deserialize)]
 pub(crate) enum PipelineStatus {
     Completed,
+    Failed,
     Pending,
     Processing,
+    TimedOut,
 }
 
 impl FromStr for PipelineStatus {
@@ -56,13 +60,20 @@ pub(crate) struct BnaPipeline {
     state_machine_id: Uuid,
     /// Pipeline status
     status: PipelineStatus,
+    /// Explanation of the last status change, i.e. why the pipeline timed out
+    #[schema(examples("no progress on step Analysis for more than 720 minutes"))]
+    status_reason: Option<String>,
     /// Last pipeline step that was completed
     step: BnaPipelineStep,
+    /// Last time the pipeline was updated
+    updated_at: Option<DateTime<chrono::FixedOffset>>,
 }
 
-impl From<entity::bna_pipeline::Model> for BnaPipeline {
-    fn from(value: entity::bna_pipeline::Model) -> Self {
-        Self {
+impl TryFrom<entity::bna_pipeline::Model> for BnaPipeline {
+    type Error = serde_plain::Error;
+
+    fn try_from(value: entity::bna_pipeline::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
             cost: value.cost,
             end_time: value.end_time,
             fargate_price_id: value.fargate_price_id,
@@ -73,9 +84,11 @@ impl From<entity::bna_pipeline::Model> for BnaPipeline {
                 .and_then(|v| serde_json::to_string(&v).ok()),
             start_time: value.start_time,
             state_machine_id: value.state_machine_id,
-            status: PipelineStatus::from_str(&value.status).expect("a valid status"),
-            step: BnaPipelineStep::from_str(&value.step).expect("a valid step"),
-        }
+            status: PipelineStatus::from_str(&value.status)?,
+            status_reason: value.status_reason,
+            step: BnaPipelineStep::from_str(&value.step)?,
+            updated_at: value.updated_at,
+        })
     }
 }
 
@@ -132,6 +145,8 @@ pub(crate) struct BnaPipelinePatch {
     start_time: Option<DateTime<chrono::FixedOffset>>,
     /// Pipeline status
     status: PipelineStatus,
+    /// Explanation of the status change
+    status_reason: Option<String>,
     /// Last pipeline step that was completed
     step: BnaPipelineStep,
 }
@@ -162,3 +177,230 @@ impl Display for BnaPipelineStep {
 #[allow(dead_code)]
 #[derive(ToSchema)]
 pub(crate) struct BnaPipelines(Vec<BnaPipeline>);
+
+/// Step of a pipeline, as exposed by the version 2 of the API.
+///
+/// Contrary to the version 1, the steps are the ones actually recorded by the
+/// pipelines.
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = BnaPipelineStep)]
+pub(crate) enum BnaPipelineStepV2 {
+    Setup,
+    Analysis,
+    Save,
+    Cleanup,
+}
+
+impl FromStr for BnaPipelineStepV2 {
+    type Err = serde_plain::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_plain::from_str::<Self>(s)
+    }
+}
+
+impl Display for BnaPipelineStepV2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_plain::to_string(&self).expect("cannot serialize value");
+        write!(f, "{value}")
+    }
+}
+
+/// Pipeline, as exposed by the version 2 of the API.
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = BnaPipeline)]
+pub(crate) struct BnaPipelineV2 {
+    /// Cost of an analysis in USD
+    #[schema(examples("6.8941"))]
+    cost: Option<Decimal>,
+    /// End time
+    end_time: Option<DateTime<chrono::FixedOffset>>,
+    /// Fargate price identifier used to compute the cost
+    fargate_price_id: Option<i32>,
+    /// ARN of the Fargate task that performed the analysis
+    #[schema(examples(
+        "arn:aws:ecs:us-west-2:123456789012:task/bna/29f979fc9fca402d94b014aa23d2f6e0"
+    ))]
+    fargate_task_arn: Option<String>,
+    /// Path of the S3 bucket where the results were stored
+    #[schema(examples("united states/new mexico/santa rosa/24.05.4"))]
+    s3_bucket: Option<String>,
+    /// Copy of the JSON message that was sent for processing
+    #[schema(examples(json!({"country":"United States","city":"santa rosa","region":"new mexico","fips_code":"3570670"})))]
+    sqs_message: Option<String>,
+    /// Start time
+    start_time: DateTime<chrono::FixedOffset>,
+    /// Pipeline identifier
+    /// This is the ID of the AWS state machine that was used to run the pipeline
+    state_machine_id: Uuid,
+    /// Pipeline status
+    status: PipelineStatus,
+    /// Explanation of the last status change, i.e. why the pipeline timed out
+    #[schema(examples("no progress on step Analysis for more than 720 minutes"))]
+    status_reason: Option<String>,
+    /// Last pipeline step that was completed
+    step: BnaPipelineStepV2,
+    /// Last time the pipeline was updated
+    updated_at: Option<DateTime<chrono::FixedOffset>>,
+}
+
+impl TryFrom<entity::bna_pipeline::Model> for BnaPipelineV2 {
+    type Error = serde_plain::Error;
+
+    fn try_from(value: entity::bna_pipeline::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            cost: value.cost,
+            end_time: value.end_time,
+            fargate_price_id: value.fargate_price_id,
+            fargate_task_arn: value.fargate_task_arn,
+            s3_bucket: value.s3_bucket,
+            sqs_message: value
+                .sqs_message
+                .and_then(|v| serde_json::to_string(&v).ok()),
+            start_time: value.start_time,
+            state_machine_id: value.state_machine_id,
+            status: PipelineStatus::from_str(&value.status)?,
+            status_reason: value.status_reason,
+            step: BnaPipelineStepV2::from_str(&value.step)?,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+/// Pipeline update, as accepted by the version 2 of the API.
+///
+/// Contrary to the version 1, the status and the step are validated when the request
+/// is parsed.
+#[derive(ToSchema, Deserialize)]
+#[schema(as = BnaPipelinePatch)]
+pub(crate) struct BnaPipelinePatchV2 {
+    /// Cost of an analysis in USD
+    #[schema(examples("6.8941"))]
+    cost: Option<Decimal>,
+    /// End time
+    end_time: Option<DateTime<chrono::FixedOffset>>,
+    /// Fargate price identifier used to compute the cost
+    fargate_price_id: Option<i32>,
+    /// ARN of the Fargate task that performed the analysis
+    #[schema(examples(
+        "arn:aws:ecs:us-west-2:123456789012:task/bna/29f979fc9fca402d94b014aa23d2f6e0"
+    ))]
+    fargate_task_arn: Option<String>,
+    /// Path of the S3 bucket where the results were stored
+    #[schema(examples("united states/new mexico/santa rosa/24.05.4"))]
+    s3_bucket: Option<String>,
+    /// Copy of the JSON message that was sent for processing
+    #[schema(value_type = Option<Object>)]
+    sqs_message: Option<serde_json::Value>,
+    /// Start time
+    start_time: Option<DateTime<chrono::FixedOffset>>,
+    /// Pipeline status
+    status: Option<PipelineStatus>,
+    /// Explanation of the status change
+    status_reason: Option<String>,
+    /// Last pipeline step that was completed
+    step: Option<BnaPipelineStepV2>,
+}
+
+impl From<BnaPipelinePatchV2> for BNAPipelinePatch {
+    fn from(value: BnaPipelinePatchV2) -> Self {
+        Self {
+            cost: value.cost.map(Some),
+            end_time: value.end_time.map(Some),
+            fargate_price_id: value.fargate_price_id.map(Some),
+            fargate_task_arn: value.fargate_task_arn.map(Some),
+            result_posted: None,
+            s3_bucket: value.s3_bucket.map(Some),
+            sqs_message: value.sqs_message.map(Some),
+            start_time: value.start_time.map(Some),
+            status: value.status.map(|v| v.to_string()),
+            status_reason: value.status_reason.map(Some),
+            step: value.step.map(|v| v.to_string()),
+            torn_down: None,
+        }
+    }
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = BnaPipelines)]
+pub(crate) struct BnaPipelinesV2(Vec<BnaPipelineV2>);
+
+impl TryFrom<Vec<entity::bna_pipeline::Model>> for BnaPipelinesV2 {
+    type Error = serde_plain::Error;
+
+    fn try_from(value: Vec<entity::bna_pipeline::Model>) -> Result<Self, Self::Error> {
+        value
+            .into_iter()
+            .map(BnaPipelineV2::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+/// Converts the stored pipelines into their schema.
+///
+/// The status and the step are stored as text, so a value which is not part of the
+/// schema, i.e. the `Save` step in the version 1, is reported as an unexpected error.
+pub(crate) fn pipeline_schema<M, S>(model: M, ctx: &Context) -> Result<S, ExecutionError>
+where
+    S: TryFrom<M, Error = serde_plain::Error>,
+{
+    S::try_from(model).map_err(|e| {
+        ExecutionError::Unexpected(
+            ctx.request_id(),
+            ctx.source(),
+            String::from("invalid pipeline status or step"),
+            e.to_string(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn pipeline(step: &str) -> entity::bna_pipeline::Model {
+        entity::bna_pipeline::Model {
+            state_machine_id: Uuid::new_v4(),
+            step: String::from(step),
+            sqs_message: None,
+            fargate_price_id: None,
+            fargate_task_arn: None,
+            s3_bucket: None,
+            status: String::from("Processing"),
+            start_time: chrono::Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
+            end_time: None,
+            cost: None,
+            status_reason: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_step_outside_of_the_schema_is_an_error() {
+        let ctx = Context::new(None, String::from("/pipelines/bna"));
+        let v1: Result<BnaPipeline, _> = pipeline_schema(pipeline("Save"), &ctx);
+        assert!(matches!(v1, Err(ExecutionError::Unexpected(..))));
+        let v2: Result<BnaPipelineV2, _> = pipeline_schema(pipeline("Save"), &ctx);
+        assert!(matches!(
+            v2,
+            Ok(BnaPipelineV2 {
+                step: BnaPipelineStepV2::Save,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_pipeline_patch_v2_validates_the_step() {
+        let patch: BnaPipelinePatchV2 =
+            serde_json::from_str(r#"{"status": "Processing", "step": "Save"}"#).unwrap();
+        let patch = BNAPipelinePatch::from(patch);
+        assert_eq!(patch.status.as_deref(), Some("Processing"));
+        assert_eq!(patch.step.as_deref(), Some("Save"));
+        assert_eq!(patch.cost, None);
+
+        assert!(serde_json::from_str::<BnaPipelinePatchV2>(r#"{"step": "SqsMessage"}"#).is_err());
+    }
+}
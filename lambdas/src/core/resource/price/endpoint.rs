@@ -1,16 +1,16 @@
 use super::adaptor::{get_price_fargate_adaptor_model_, get_prices_fargate_adaptor};
 use crate::{
-    core::resource::{
-        price::schema::{FargatePrice, FargatePrices},
-        schema::{ErrorResponses, ListParameters},
+    core::{
+        extract::{Json, Path, Query},
+        metrics,
+        resource::{
+            price::schema::{FargatePrice, FargatePrices},
+            schema::{ErrorResponses, ListParameters},
+        },
     },
-    database_connect_or_init, PageFlow, Paginatron,
+    database_read_connect_or_init, PageFlow, Paginatron,
 };
 use crate::{Context, ExecutionError};
-use axum::{
-    extract::{Path, Query},
-    Json,
-};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 const TAG: &str = "price";
@@ -36,14 +36,17 @@ pub fn routes() -> OpenApiRouter {
 pub(crate) async fn get_prices_fargate(
     Query(list): Query<ListParameters>,
 ) -> Result<PageFlow<FargatePrices>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let (total_items, models) = get_prices_fargate_adaptor(
-        db,
-        list.order_direction(),
-        &list.sort_by(),
-        list.latest(),
-        list.page(),
-        list.page_size(),
+    let db = &database_read_connect_or_init().await?;
+    let (total_items, models) = metrics::adaptor(
+        "get_prices_fargate_adaptor",
+        get_prices_fargate_adaptor(
+            db,
+            list.order_direction(),
+            &list.sort_by(),
+            list.latest(),
+            list.page(),
+            list.page_size(),
+        ),
     )
     .await?;
     let payload: FargatePrices = models.into();
@@ -69,7 +72,7 @@ pub(crate) async fn get_price_fargate(
     Path(price_id): Path<i32>,
     ctx: Context,
 ) -> Result<Json<FargatePrice>, ExecutionError> {
-    let db = database_connect_or_init().await?;
+    let db = &database_read_connect_or_init().await?;
     get_price_fargate_adaptor_model_(db, price_id, ctx)
         .await
         .map(FargatePrice::from)
@@ -1,13 +1,20 @@
 use super::{
-    db::{fetch_rating, fetch_ratings, fetch_ratings_city, fetch_ratings_summaries, Bna},
+    db::{
+        fetch_rating, fetch_ratings, fetch_ratings_city, fetch_ratings_pipeline,
+        fetch_ratings_summaries, Bna,
+    },
     schema::RatingPost,
 };
-use crate::{Context, ExecutionError};
+use crate::{
+    core::{response_cache::invalidate, webhook::record_event},
+    Context, ExecutionError,
+};
 use entity::{
     core_services, infrastructure, measure, opportunity, people, recreation, retail, summary,
-    transit,
+    transit, wrappers::WebhookEvent,
 };
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection};
+use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, TransactionTrait};
+use serde_json::json;
 use tracing::info;
 use uuid::Uuid;
 
@@ -63,6 +70,28 @@ pub(crate) async fn get_ratings_city_adaptor(
     }
 }
 
+pub(crate) async fn get_ratings_pipeline_adaptor(
+    db: &DatabaseConnection,
+    rating_id: Uuid,
+    ctx: Context,
+) -> Result<entity::bna_pipeline::Model, ExecutionError> {
+    // Fetch the rating and its pipeline.
+    let model = fetch_ratings_pipeline(db, rating_id).await?;
+    match model {
+        Some((_, Some(pipeline))) => Ok(pipeline),
+        Some((_, None)) => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("the rating with the ID {rating_id} is not linked to a pipeline"),
+        )),
+        None => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find a rating with the ID {rating_id}"),
+        )),
+    }
+}
+
 pub(crate) async fn post_ratings_adaptor(
     db: &DatabaseConnection,
     rating: RatingPost,
@@ -82,6 +111,7 @@ pub(crate) async fn post_ratings_adaptor(
         residential_speed_limit_override: rating
             .speed_limit_override
             .map_or(ActiveValue::NotSet, |v| ActiveValue::Set(Some(v))),
+        pipeline_id: ActiveValue::Set(rating.pipeline_id),
     };
     info!("{:?}", summary);
     let core_services = core_services::ActiveModel {
@@ -173,15 +203,16 @@ pub(crate) async fn post_ratings_adaptor(
     info!("{:?}", measure);
 
     // And insert a new entry for each model.
-    let summary_model = summary.insert(db).await?;
-    let core_services_model = core_services.insert(db).await?;
-    let infrastructure_model = infrastructure.insert(db).await?;
-    let opportunity_model = opportunity.insert(db).await?;
-    let people_model = people.insert(db).await?;
-    let recreation_model = recreation.insert(db).await?;
-    let retail_model = retail.insert(db).await?;
-    let transit_model = transit.insert(db).await?;
-    let measure_model = measure.insert(db).await?;
+    let txn = db.begin().await?;
+    let summary_model = summary.insert(&txn).await?;
+    let core_services_model = core_services.insert(&txn).await?;
+    let infrastructure_model = infrastructure.insert(&txn).await?;
+    let opportunity_model = opportunity.insert(&txn).await?;
+    let people_model = people.insert(&txn).await?;
+    let recreation_model = recreation.insert(&txn).await?;
+    let retail_model = retail.insert(&txn).await?;
+    let transit_model = transit.insert(&txn).await?;
+    let measure_model = measure.insert(&txn).await?;
     let bna = Bna {
         id: summary_model.id,
         city_id: summary_model.city_id,
@@ -189,6 +220,8 @@ pub(crate) async fn post_ratings_adaptor(
         population: summary_model.population,
         score: summary_model.score,
         version: summary_model.version,
+        pipeline_id: summary_model.pipeline_id,
+        created_at: summary_model.created_at,
         low_stress_miles: infrastructure_model.low_stress_miles,
         high_stress_miles: infrastructure_model.high_stress_miles,
         community_centers: recreation_model.community_centers,
@@ -217,5 +250,22 @@ pub(crate) async fn post_ratings_adaptor(
         track: measure_model.track,
     };
     info!("{:?}", bna);
+
+    // Notify the subscribers.
+    record_event(
+        &txn,
+        WebhookEvent::RatingCreated,
+        json!({
+            "rating_id": bna.id,
+            "city_id": bna.city_id,
+            "pipeline_id": bna.pipeline_id,
+            "score": bna.score,
+            "version": bna.version,
+        }),
+    )
+    .await?;
+    txn.commit().await?;
+    invalidate();
+
     Ok(bna)
 }
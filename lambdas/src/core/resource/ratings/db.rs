@@ -1,7 +1,7 @@
-use entity::{city, summary};
+use entity::{bna_pipeline, city, summary};
 use sea_orm::{
-    DatabaseConnection, EntityTrait, FromQueryResult, JoinType, PaginatorTrait, QuerySelect,
-    RelationTrait,
+    prelude::DateTimeWithTimeZone, DatabaseConnection, EntityTrait, FromQueryResult, JoinType,
+    PaginatorTrait, QuerySelect, RelationTrait,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -15,6 +15,8 @@ pub(crate) struct Bna {
     pub(crate) pop_size: i32,
     pub(crate) population: i32,
     pub(crate) version: String,
+    pub(crate) pipeline_id: Option<Uuid>,
+    pub(crate) created_at: DateTimeWithTimeZone,
 
     // BNAInfrastructure
     pub(crate) low_stress_miles: Option<f64>,
@@ -94,6 +96,16 @@ pub async fn fetch_ratings_city(
     }
 }
 
+pub async fn fetch_ratings_pipeline(
+    db: &DatabaseConnection,
+    rating_id: Uuid,
+) -> Result<Option<(summary::Model, Option<bna_pipeline::Model>)>, sea_orm::DbErr> {
+    summary::Entity::find_by_id(rating_id)
+        .find_also_related(bna_pipeline::Entity)
+        .one(db)
+        .await
+}
+
 pub async fn fetch_rating(
     db: &DatabaseConnection,
     rating_id: Uuid,
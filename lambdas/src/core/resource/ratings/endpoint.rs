@@ -1,18 +1,27 @@
 use super::{
     adaptor::{
-        get_rating_adaptor, get_ratings_adaptor, get_ratings_city_adaptor, post_ratings_adaptor,
+        get_rating_adaptor, get_ratings_adaptor, get_ratings_city_adaptor,
+        get_ratings_pipeline_adaptor, post_ratings_adaptor,
+    },
+    schema::{
+        Rating, RatingPost, RatingPostV2, RatingV2, RatingWithCity, RatingWithCityV2, Ratings,
+        RatingsV2,
     },
-    schema::{Rating, RatingPost, RatingWithCity, Ratings},
 };
 use crate::{
-    core::resource::schema::{ErrorResponses, PaginationParameters},
-    database_connect_or_init, Context, ExecutionError, PageFlow, Paginatron,
-};
-use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    Json,
+    core::{
+        extract::{Json, Path, Query},
+        http_cache::http_date,
+        metrics,
+        resource::{
+            pipelines::schema::{pipeline_schema, BnaPipeline, BnaPipelineV2},
+            schema::{ErrorResponses, PaginationParameters},
+        },
+    },
+    database_connect_or_init, database_read_connect_or_init, Context, ExecutionError, PageFlow,
+    Paginatron,
 };
+use axum::http::{header, HeaderName, StatusCode};
 use tracing::debug;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
@@ -20,111 +29,196 @@ use uuid::Uuid;
 const TAG: &str = "rating";
 
 pub fn routes() -> OpenApiRouter {
-    OpenApiRouter::new()
-        .routes(routes!(get_rating))
-        .routes(routes!(get_ratings))
-        .routes(routes!(post_rating))
-        .routes(routes!(get_ratings_city))
+    v1::routes()
 }
 
-#[utoipa::path(
-  get,
-  path = "/ratings/{rating_id}",
-  description = "Get the details of a specific city rating",
-  tag = TAG,
-  params(
-    ("rating_id" = Uuid, Path, description = "Rating identifier")
-  ),
-  responses(
-    (status = OK, description = "Fetches the details of a city rating", body = Rating ),
-    ErrorResponses,
-  ))]
-async fn get_rating(
-    Path(rating_id): Path<Uuid>,
-    ctx: Context,
-) -> Result<Json<Rating>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_rating_adaptor(db, rating_id, ctx)
-        .await
-        .map_err(|e| {
-            debug!("{e}");
-            e
-        })
-        .map(Rating::from)
-        .map(Json)
+pub fn routes_v2() -> OpenApiRouter {
+    v2::routes()
 }
 
-#[utoipa::path(
-  get,
-  path = "/ratings",
-  description = "Get city ratings",
-  tag = TAG,
-  params(
-    PaginationParameters,
-  ),
-  responses(
-    (status = OK, description = "Fetches the city ratings", body = Ratings),
-  ))]
-async fn get_ratings(
-    Query(pagination): Query<PaginationParameters>,
-) -> Result<PageFlow<Ratings>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let (total_items, models) =
-        get_ratings_adaptor(db, pagination.page(), pagination.page_size()).await?;
+/// Declares the rating endpoints of a version of the API.
+///
+/// The versions share the adaptors and only differ by the schemas the results are
+/// converted to, so every version expands the same handlers in its own module.
+macro_rules! rating_endpoints {
+    (
+        rating: $rating:ident,
+        ratings: $ratings:ident,
+        rating_post: $rating_post:ident,
+        rating_with_city: $rating_with_city:ident,
+        pipeline: $pipeline:ident $(,)?
+    ) => {
+        pub(super) fn routes() -> OpenApiRouter {
+            OpenApiRouter::new()
+                .routes(routes!(get_rating))
+                .routes(routes!(get_ratings))
+                .routes(routes!(post_rating))
+                .routes(routes!(get_ratings_city))
+                .routes(routes!(get_ratings_pipeline))
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/ratings/{rating_id}",
+          description = "Get the details of a specific city rating",
+          tag = TAG,
+          params(
+            ("rating_id" = Uuid, Path, description = "Rating identifier")
+          ),
+          responses(
+            (status = OK, description = "Fetches the details of a city rating", body = $rating),
+            ErrorResponses,
+          ))]
+        async fn get_rating(
+            Path(rating_id): Path<Uuid>,
+            ctx: Context,
+        ) -> Result<([(HeaderName, String); 1], Json<$rating>), ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            metrics::adaptor("get_rating_adaptor", get_rating_adaptor(db, rating_id, ctx))
+                .await
+                .map_err(|e| {
+                    debug!("{e}");
+                    e
+                })
+                .map(|bna| {
+                    (
+                        [(header::LAST_MODIFIED, http_date(&bna.created_at))],
+                        Json($rating::from(bna)),
+                    )
+                })
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/ratings",
+          description = "Get city ratings",
+          tag = TAG,
+          params(
+            PaginationParameters,
+          ),
+          responses(
+            (status = OK, description = "Fetches the city ratings", body = $ratings),
+          ))]
+        async fn get_ratings(
+            Query(pagination): Query<PaginationParameters>,
+        ) -> Result<PageFlow<$ratings>, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            let (total_items, models) = metrics::adaptor(
+                "get_ratings_adaptor",
+                get_ratings_adaptor(db, pagination.page(), pagination.page_size()),
+            )
+            .await?;
 
-    let payload: Ratings = models.into();
-    Ok(PageFlow::new(
-        Paginatron::new(None, total_items, pagination.page(), pagination.page_size()),
-        payload,
-    ))
+            let payload: $ratings = models.into();
+            Ok(PageFlow::new(
+                Paginatron::new(None, total_items, pagination.page(), pagination.page_size()),
+                payload,
+            ))
+        }
+
+        #[utoipa::path(
+          post,
+          path = "/ratings",
+          description = "Create a new city rating",
+          tag = TAG,
+          request_body = $rating_post,
+          responses(
+            (status = CREATED, description = "Creates a new city rating", body = $rating),
+            ErrorResponses,
+          ))]
+        async fn post_rating(
+            Json(bna): Json<$rating_post>,
+        ) -> Result<(StatusCode, Json<$rating>), ExecutionError> {
+            let db = &database_connect_or_init().await?;
+            metrics::adaptor(
+                "post_ratings_adaptor",
+                post_ratings_adaptor(db, RatingPost::from(bna)),
+            )
+            .await
+            .map_err(|e| {
+                debug!("{e}");
+                e
+            })
+            .map($rating::from)
+            .map(|v| (StatusCode::CREATED, Json(v)))
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/ratings/{rating_id}/city",
+          description = "Get a city rating and its associated city details",
+          tag = TAG,
+          params(
+            ("rating_id" = Uuid, Path, description = "Rating identifier")
+          ),
+          responses(
+            (status = OK, description = "Fetches a city rating and its associated city details" , body = $rating_with_city),
+            ErrorResponses,
+          ))]
+        async fn get_ratings_city(
+            Path(rating_id): Path<Uuid>,
+            ctx: Context,
+        ) -> Result<Json<$rating_with_city>, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            metrics::adaptor(
+                "get_ratings_city_adaptor",
+                get_ratings_city_adaptor(db, rating_id, ctx),
+            )
+            .await
+            .map(|(rating, model)| $rating_with_city {
+                rating: rating.into(),
+                city: model.into(),
+            })
+            .map(Json)
+        }
+
+        #[utoipa::path(
+          get,
+          path = "/ratings/{rating_id}/pipeline",
+          description = "Get the details of the BNA pipeline which produced a city rating",
+          tag = TAG,
+          params(
+            ("rating_id" = Uuid, Path, description = "Rating identifier")
+          ),
+          responses(
+            (status = OK, description = "Fetches the details of the BNA pipeline which produced a city rating", body = $pipeline),
+            ErrorResponses,
+          ))]
+        async fn get_ratings_pipeline(
+            Path(rating_id): Path<Uuid>,
+            ctx: Context,
+        ) -> Result<Json<$pipeline>, ExecutionError> {
+            let db = &database_read_connect_or_init().await?;
+            let model = metrics::adaptor(
+                "get_ratings_pipeline_adaptor",
+                get_ratings_pipeline_adaptor(db, rating_id, ctx.clone()),
+            )
+            .await?;
+            pipeline_schema(model, &ctx).map(Json)
+        }
+    };
 }
 
-#[utoipa::path(
-  post,
-  path = "/ratings",
-  description = "Create a new city rating",
-  tag = TAG,
-  request_body = RatingPost,
-  responses(
-    (status = CREATED, description = "Creates a new city rating", body = Rating ),
-    ErrorResponses,
-  ))]
-async fn post_rating(
-    Json(bna): Json<RatingPost>,
-) -> Result<(StatusCode, Json<Rating>), ExecutionError> {
-    let db = database_connect_or_init().await?;
-    post_ratings_adaptor(db, bna)
-        .await
-        .map_err(|e| {
-            debug!("{e}");
-            e
-        })
-        .map(Rating::from)
-        .map(|v| (StatusCode::CREATED, Json(v)))
+mod v1 {
+    use super::*;
+
+    rating_endpoints!(
+        rating: Rating,
+        ratings: Ratings,
+        rating_post: RatingPost,
+        rating_with_city: RatingWithCity,
+        pipeline: BnaPipeline,
+    );
 }
 
-#[utoipa::path(
-  get,
-  path = "/ratings/{rating_id}/city",
-  description = "Get a city rating and its associated city details",
-  tag = TAG,
-  params(
-    ("rating_id" = Uuid, Path, description = "Rating identifier")
-  ),
-  responses(
-    (status = OK, description = "Fetches a city rating and its associated city details" , body = RatingWithCity ),
-    ErrorResponses,
-  ))]
-async fn get_ratings_city(
-    Path(rating_id): Path<Uuid>,
-    ctx: Context,
-) -> Result<Json<RatingWithCity>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_ratings_city_adaptor(db, rating_id, ctx)
-        .await
-        .map(|(rating, model)| RatingWithCity {
-            rating: rating.into(),
-            city: model.into(),
-        })
-        .map(Json)
+mod v2 {
+    use super::*;
+
+    rating_endpoints!(
+        rating: RatingV2,
+        ratings: RatingsV2,
+        rating_post: RatingPostV2,
+        rating_with_city: RatingWithCityV2,
+        pipeline: BnaPipelineV2,
+    );
 }
@@ -10,7 +10,9 @@ use uuid::Uuid;
 pub mod adaptor;
 mod db;
 pub mod endpoint;
-mod schema;
+pub(crate) mod schema;
+
+pub(crate) use db::Bna;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BNAComponent {
@@ -19,6 +19,8 @@ pub(crate) struct Rating {
     /// The format follows the [calver](https://calver.org) specification with
     /// the YY.0M[.Minor] scheme.
     version: String,
+    /// Identifier of the pipeline which produced the rating, if any
+    pipeline_id: Option<Uuid>,
 
     /// BNAInfrastructure
     infrastructure: Infrastructure,
@@ -52,6 +54,7 @@ impl From<Bna> for Rating {
             city_id: value.city_id,
             score: value.score,
             version: value.version,
+            pipeline_id: value.pipeline_id,
             infrastructure: Infrastructure {
                 low_stress_miles: value.low_stress_miles,
                 high_stress_miles: value.high_stress_miles,
@@ -221,6 +224,8 @@ pub(crate) struct RatingPost {
     pub(crate) population: i32,
     /// Residential speed limit, if any.
     pub(crate) speed_limit_override: Option<i32>,
+    /// Identifier of the pipeline which produced the rating, if any.
+    pub(crate) pipeline_id: Option<Uuid>,
 
     /// BNAInfrastructure
     pub(crate) infrastructure: Infrastructure,
@@ -265,3 +270,206 @@ pub(crate) struct Measure {
     #[schema(examples(53.859))]
     pub(crate) track: Option<f64>,
 }
+
+/// City rating, as exposed by the version 2 of the API.
+///
+/// The category scores are nested under `categories` and the bike infrastructure
+/// measures under `infrastructure`.
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = Rating)]
+pub(crate) struct RatingV2 {
+    /// Rating identifier
+    id: Uuid,
+    /// City identifier
+    city_id: Uuid,
+    /// Total rating score
+    #[schema(minimum = 0, maximum = 100)]
+    score: f64,
+    /// Rating version
+    /// The format follows the [calver](https://calver.org) specification with
+    /// the YY.0M[.Minor] scheme.
+    version: String,
+    /// Identifier of the pipeline which produced the rating, if any
+    pipeline_id: Option<Uuid>,
+    /// Scores of the BNA categories
+    categories: Categories,
+    /// Bike infrastructure of the city
+    infrastructure: InfrastructureV2,
+}
+
+impl From<Bna> for RatingV2 {
+    fn from(value: Bna) -> Self {
+        Self {
+            id: value.id,
+            city_id: value.city_id,
+            score: value.score,
+            version: value.version,
+            pipeline_id: value.pipeline_id,
+            categories: Categories {
+                core_services: CoreServices {
+                    dentists: value.dentists,
+                    doctors: value.doctors,
+                    grocery: value.grocery,
+                    hospitals: value.hospitals,
+                    pharmacies: value.pharmacies,
+                    score: value.coreservices_score,
+                    social_services: value.social_services,
+                },
+                opportunity: Opportunity {
+                    employment: value.employment,
+                    higher_education: value.higher_education,
+                    k12_education: value.k12_education,
+                    score: value.opportunity_score,
+                    technical_vocational_college: value.technical_vocational_college,
+                },
+                people: CategoryScore {
+                    score: value.people_score,
+                },
+                recreation: Recreation {
+                    community_centers: value.community_centers,
+                    parks: value.parks,
+                    trails: value.recreation_trails,
+                    score: value.recreation_score,
+                },
+                retail: CategoryScore {
+                    score: value.retail_score,
+                },
+                transit: CategoryScore {
+                    score: value.transit_score,
+                },
+            },
+            infrastructure: InfrastructureV2 {
+                low_stress_miles: value.low_stress_miles,
+                high_stress_miles: value.high_stress_miles,
+                measure: Measure {
+                    buffered_lane: value.buffered_lane,
+                    lane: value.lane,
+                    path: value.path,
+                    sharrow: value.sharrow,
+                    track: value.track,
+                },
+            },
+        }
+    }
+}
+
+/// Scores of the BNA categories.
+#[derive(ToSchema, Serialize, Deserialize)]
+pub(crate) struct Categories {
+    /// BNA Core Services
+    pub(crate) core_services: CoreServices,
+    /// BNA Opportunity
+    pub(crate) opportunity: Opportunity,
+    /// BNA People
+    pub(crate) people: CategoryScore,
+    /// BNA Recreation
+    pub(crate) recreation: Recreation,
+    /// BNA Retail
+    pub(crate) retail: CategoryScore,
+    /// BNA Transit
+    pub(crate) transit: CategoryScore,
+}
+
+/// Score of a BNA category without subscores.
+#[derive(ToSchema, Serialize, Deserialize)]
+pub(crate) struct CategoryScore {
+    /// BNA category score.
+    #[schema(minimum = 0, maximum = 100)]
+    pub(crate) score: Option<f64>,
+}
+
+/// Bike infrastructure of a city, as exposed by the version 2 of the API.
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = Infrastructure)]
+pub(crate) struct InfrastructureV2 {
+    /// Total miles of low-stress streets and paths in the measured area.
+    #[schema(examples(127))]
+    pub(crate) low_stress_miles: Option<f64>,
+    /// Total miles of high-stress streets in the measured area.
+    #[schema(examples(253))]
+    pub(crate) high_stress_miles: Option<f64>,
+    /// Measurement of the city's bike infrastructure.
+    pub(crate) measure: Measure,
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = Ratings)]
+pub(crate) struct RatingsV2(Vec<RatingV2>);
+
+impl From<Vec<Bna>> for RatingsV2 {
+    fn from(value: Vec<Bna>) -> Self {
+        Self(value.into_iter().map(RatingV2::from).collect())
+    }
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = RatingWithCity)]
+pub(crate) struct RatingWithCityV2 {
+    pub(crate) rating: RatingV2,
+    pub(crate) city: City,
+}
+
+/// City rating to create, as accepted by the version 2 of the API.
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(as = RatingPost)]
+pub(crate) struct RatingPostV2 {
+    /// City identifier
+    pub(crate) city_id: Uuid,
+    /// Rating version
+    /// The format follows the [calver](https://calver.org) specification with
+    /// the YY.0M[.Minor] scheme.
+    pub(crate) version: String,
+    /// City rating score
+    pub(crate) score: f64,
+    /// City population size category (small, medium, large).
+    pub(crate) pop_size: i32,
+    /// City population based on the annual U.S. Census American Community Survey.
+    pub(crate) population: i32,
+    /// Residential speed limit, if any.
+    pub(crate) speed_limit_override: Option<i32>,
+    /// Identifier of the pipeline which produced the rating, if any.
+    pub(crate) pipeline_id: Option<Uuid>,
+    /// Scores of the BNA categories
+    pub(crate) categories: Categories,
+    /// Bike infrastructure of the city
+    pub(crate) infrastructure: InfrastructureV2,
+}
+
+impl From<RatingPostV2> for RatingPost {
+    fn from(value: RatingPostV2) -> Self {
+        let Categories {
+            core_services,
+            opportunity,
+            people,
+            recreation,
+            retail,
+            transit,
+        } = value.categories;
+        Self {
+            city_id: value.city_id,
+            version: value.version,
+            score: value.score,
+            pop_size: value.pop_size,
+            population: value.population,
+            speed_limit_override: value.speed_limit_override,
+            pipeline_id: value.pipeline_id,
+            infrastructure: Infrastructure {
+                low_stress_miles: value.infrastructure.low_stress_miles,
+                high_stress_miles: value.infrastructure.high_stress_miles,
+            },
+            recreation,
+            opportunity,
+            core_services,
+            people: People {
+                people: people.score,
+            },
+            retail: Retail {
+                retail: retail.score,
+            },
+            transit: Transit {
+                transit: transit.score,
+            },
+            measure: value.infrastructure.measure,
+        }
+    }
+}
@@ -4,17 +4,20 @@ use super::{
     db::{fetch_all_reports, fetch_report_year},
     schema::BnaReport,
 };
-use crate::ExecutionError;
+use crate::{core::response_cache::cached, ExecutionError};
 
 pub(crate) async fn get_report_adaptor(
     db: &DatabaseConnection,
     year: u32,
 ) -> Result<Vec<BnaReport>, ExecutionError> {
-    Ok(fetch_report_year(db, year).await?)
+    Ok(cached("report_year", &format!("/reports/{year}"), || {
+        fetch_report_year(db, year)
+    })
+    .await?)
 }
 
 pub(crate) async fn get_reports_adaptor(
     db: &DatabaseConnection,
 ) -> Result<Vec<BnaReport>, ExecutionError> {
-    Ok(fetch_all_reports(db).await?)
+    Ok(cached("reports", "/reports", || fetch_all_reports(db)).await?)
 }
@@ -22,6 +22,7 @@ pub(crate) async fn fetch_report_year(
 
       -- Summary version info (simple)
       s.version,
+      s.created_at,
       s.pop_size,
       s.population,
       s.residential_speed_limit_override,
@@ -101,6 +102,7 @@ pub(crate) async fn fetch_all_reports(
 
     -- Summary version info (simple)
     summary.version,
+    summary.created_at,
     summary.pop_size,
     summary.population,
     summary.residential_speed_limit_override,
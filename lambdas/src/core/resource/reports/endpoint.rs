@@ -1,7 +1,13 @@
-use crate::{database_connect_or_init, ExecutionError};
+use crate::{
+    core::{extract::Path, http_cache::last_modified, metrics},
+    database_read_connect_or_init, ExecutionError,
+};
 
-use super::adaptor::{get_report_adaptor, get_reports_adaptor};
-use axum::extract::Path;
+use super::{
+    adaptor::{get_report_adaptor, get_reports_adaptor},
+    schema::BnaReport,
+};
+use axum::{http::HeaderName, response::AppendHeaders};
 use axum_streams::*;
 use futures::{stream, StreamExt};
 use utoipa_axum::router::OpenApiRouter;
@@ -24,14 +30,12 @@ pub fn routes() -> OpenApiRouter {
     (status = OK, description = "Fetches all rating reports in csv format", content_type = "text/csv"),
   )
 )]
-async fn get_reports() -> Result<StreamBodyAs<'static>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let ratings = get_reports_adaptor(db).await.expect("reports");
-    let stream = stream::iter(ratings);
-    Ok(StreamBodyAs::new(
-        CsvStreamFormat::new(true, b','),
-        stream.map(Ok::<_, axum::Error>),
-    ))
+async fn get_reports() -> Result<Report, ExecutionError> {
+    let db = &database_read_connect_or_init().await?;
+    let ratings = metrics::adaptor("get_reports_adaptor", get_reports_adaptor(db))
+        .await
+        .expect("reports");
+    Ok(csv_report(ratings))
 }
 
 #[utoipa::path(
@@ -46,12 +50,29 @@ async fn get_reports() -> Result<StreamBodyAs<'static>, ExecutionError> {
     (status = OK, description = "Fetches the latest rating reports for a specific year in csv format", content_type = "text/csv"),
   )
 )]
-async fn get_reports_year(Path(year): Path<u32>) -> Result<StreamBodyAs<'static>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let ratings = get_report_adaptor(db, year).await.expect("reports");
+async fn get_reports_year(Path(year): Path<u32>) -> Result<Report, ExecutionError> {
+    let db = &database_read_connect_or_init().await?;
+    let ratings = metrics::adaptor("get_report_adaptor", get_report_adaptor(db, year))
+        .await
+        .expect("reports");
+    Ok(csv_report(ratings))
+}
+
+/// CSV report, dated by its most recent rating.
+type Report = (
+    AppendHeaders<Option<(HeaderName, String)>>,
+    StreamBodyAs<'static>,
+);
+
+/// Streams the ratings as a CSV report.
+fn csv_report(ratings: Vec<BnaReport>) -> Report {
+    let modified = last_modified(ratings.iter().map(|r| r.created_at));
     let stream = stream::iter(ratings);
-    Ok(StreamBodyAs::new(
-        CsvStreamFormat::new(true, b','),
-        stream.map(Ok::<_, axum::Error>),
-    ))
+    (
+        AppendHeaders(modified),
+        StreamBodyAs::new(
+            CsvStreamFormat::new(true, b','),
+            stream.map(Ok::<_, axum::Error>),
+        ),
+    )
 }
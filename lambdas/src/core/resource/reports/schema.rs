@@ -1,15 +1,18 @@
-use sea_orm::FromQueryResult;
+use sea_orm::{prelude::DateTimeWithTimeZone, FromQueryResult};
 use serde::Serialize;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, FromQueryResult, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromQueryResult, Serialize, ToSchema)]
 #[schema(description = "A city rating report.")]
 pub struct BnaReport {
     // BNA Summary
     pub(crate) id: Uuid,
     pub(crate) score: f64,
     pub(crate) version: String,
+    /// Creation date of the summary, only used to date the report.
+    #[serde(skip)]
+    pub(crate) created_at: DateTimeWithTimeZone,
 
     // BNAInfrastructure
     pub(crate) low_stress_miles: Option<f64>,
@@ -1,12 +1,16 @@
 //! Describes the schemas shared accross resources.
-use crate::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::core::config::config;
 use chrono::DateTime;
+use effortless::error::PROBLEM_JSON;
 use entity::city;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::convert::Into;
 use std::{fmt::Display, str::FromStr};
-use utoipa::{IntoParams, IntoResponses, ToSchema};
+use utoipa::{
+    openapi::{Content, OpenApi, Ref, RefOr},
+    IntoParams, IntoResponses, ToSchema,
+};
 use uuid::Uuid;
 
 #[derive(ToSchema, Serialize, Deserialize)]
@@ -395,6 +399,82 @@ pub struct APIErrors {
     pub errors: Vec<APIError>,
 }
 
+/// Problem details object as described in <https://www.rfc-editor.org/rfc/rfc9457>.
+///
+/// Errors are rendered this way when the request prefers `application/problem+json`.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type, or `about:blank` for the problems
+    /// only described by their status code.
+    #[serde(rename = "type")]
+    #[schema(examples("https://api.bna.peopleforbikes.org/problems/validation"))]
+    problem_type: String,
+    /// The reason phrase of the status code.
+    #[schema(examples("Bad Request"))]
+    title: String,
+    /// The HTTP status code generated by the origin server for this occurrence.
+    #[schema(examples(400))]
+    status: u16,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[schema(examples("the request contains 2 errors"))]
+    detail: String,
+    /// A URI reference identifying the specific occurrence of the problem, i.e. the
+    /// path of the request.
+    #[schema(examples("/cities/submissions"))]
+    instance: Option<String>,
+    /// The identifier of the request the problem occurred on.
+    #[schema(examples("blfwkg8nvHcEJnQ="))]
+    request_id: Option<String>,
+    /// The errors of the request, with the parts of the request they point to.
+    errors: Option<Vec<ProblemFieldError>>,
+}
+
+/// Error of an individual part of the request.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ProblemFieldError {
+    /// A human-readable explanation of the error.
+    #[schema(examples("the email address is invalid"))]
+    detail: String,
+    /// A JSON Pointer [RFC6901] to the value in the request document that caused the error.
+    #[schema(examples("/email"))]
+    pointer: Option<String>,
+    /// A string indicating which URI query parameter caused the error.
+    parameter: Option<String>,
+    /// A string indicating the name of a single request header which caused the error.
+    header: Option<String>,
+}
+
+/// Documents the problem details alternative of every error response.
+pub fn document_problem_details(api: &mut OpenApi) {
+    for item in api.paths.paths.values_mut() {
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ];
+        for operation in operations.into_iter().flatten() {
+            for (status, response) in operation.responses.responses.iter_mut() {
+                let RefOr::T(response) = response else {
+                    continue;
+                };
+                if status.starts_with(['4', '5']) {
+                    response.content.insert(
+                        PROBLEM_JSON.to_string(),
+                        Content::new(Some(Ref::from_schema_name("ProblemDetails"))),
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Serialize, IntoResponses, ToSchema)]
 pub(crate) enum ErrorResponses {
@@ -479,7 +559,7 @@ pub(crate) struct PaginationParameters {
 impl Default for PaginationParameters {
     fn default() -> Self {
         Self {
-            page_size: Some(DEFAULT_PAGE_SIZE),
+            page_size: Some(config().pagination.default_page_size),
             page: Some(0),
         }
     }
@@ -487,14 +567,7 @@ impl Default for PaginationParameters {
 
 impl PaginationParameters {
     pub fn page_size(&self) -> u64 {
-        match self.page_size {
-            Some(value) => match value {
-                0 => 1,
-                1..=MAX_PAGE_SIZE => value,
-                _ => MAX_PAGE_SIZE,
-            },
-            None => DEFAULT_PAGE_SIZE,
-        }
+        config().pagination.page_size(self.page_size)
     }
 
     pub fn page(&self) -> u64 {
@@ -592,14 +665,7 @@ impl ListParameters {
     }
 
     pub fn page_size(&self) -> u64 {
-        match self.page_size {
-            Some(value) => match value {
-                0 => 1,
-                1..=MAX_PAGE_SIZE => value,
-                _ => MAX_PAGE_SIZE,
-            },
-            None => DEFAULT_PAGE_SIZE,
-        }
+        config().pagination.page_size(self.page_size)
     }
 
     pub fn sort_by(&self) -> String {
@@ -0,0 +1,201 @@
+use super::{
+    db::{count_pending_submissions, count_processing_pipelines},
+    schema::{DependencyHealth, Health, HealthStatus},
+};
+use crate::{
+    core::{
+        config::config,
+        metrics::{self, PIPELINES_PROCESSING, SUBMISSIONS_PENDING},
+        queue::analysis_queue_or_init,
+    },
+    database_connect_or_init, database_read_connect_or_init, database_url,
+};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tokio::time::{timeout, Instant};
+use tracing::warn;
+
+/// Maximum time allowed to check a dependency.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Whether the database was found fully migrated.
+///
+/// The migrations a process expects are built into it, therefore once they are all
+/// applied, they remain so for the life of the process.
+static MIGRATED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the liveness of the API.
+///
+/// The API is alive as long as it can process a request, therefore no dependency is
+/// checked.
+pub(crate) fn get_liveness_adaptor() -> Health {
+    Health::new(BTreeMap::new())
+}
+
+/// Returns the readiness of the API.
+///
+/// The API is ready when the database can be reached and is fully migrated, and when
+/// the read replica and the analysis queue, if configured, can be reached.
+pub(crate) async fn get_readiness_adaptor() -> Health {
+    let mut checks = BTreeMap::new();
+    let (database, db) = check("database", async {
+        let db = database_connect_or_init()
+            .await
+            .map_err(|e| e.to_string())?;
+        db.ping().await.map_err(|e| e.to_string())?;
+        Ok(db)
+    })
+    .await;
+    checks.insert("database".to_string(), database);
+    let migrations = match &db {
+        Some(_) => check("migrations", check_migrations()).await.0,
+        None => DependencyHealth {
+            status: HealthStatus::Down,
+            latency_ms: 0,
+            details: Some(String::from("the database cannot be reached")),
+        },
+    };
+    checks.insert("migrations".to_string(), migrations);
+    if config().database.read_url.is_some() {
+        let (replica, _) = check("replica", async {
+            let db = database_read_connect_or_init()
+                .await
+                .map_err(|e| e.to_string())?;
+            db.ping().await.map_err(|e| e.to_string())
+        })
+        .await;
+        checks.insert("replica".to_string(), replica);
+    }
+    if config().queue.analysis_url.is_some() {
+        let (queue, _) = check("queue", async {
+            analysis_queue_or_init()
+                .await
+                .check()
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await;
+        checks.insert("queue".to_string(), queue);
+    }
+    Health::new(checks)
+}
+
+/// Returns the metrics of the API in the Prometheus text format.
+///
+/// The domain gauges are refreshed first, on a best effort basis.
+pub(crate) async fn get_metrics_adaptor() -> String {
+    match database_connect_or_init().await {
+        Ok(db) => {
+            if let Err(e) = refresh_domain_gauges(&db).await {
+                warn!("cannot refresh the domain gauges: {e}");
+            }
+        }
+        Err(e) => warn!("cannot refresh the domain gauges: {e}"),
+    }
+    metrics::registry().render()
+}
+
+/// Refreshes the gauges tracking the state of the domain.
+///
+/// The Lambda mode having no scraper, the gauges are refreshed by the scheduled
+/// pipeline sweeper instead of at scrape time.
+pub async fn refresh_domain_gauges(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let pending = count_pending_submissions(db).await?;
+    metrics::set_gauge(SUBMISSIONS_PENDING, pending as f64);
+    let processing = count_processing_pipelines(db).await?;
+    metrics::set_gauge(PIPELINES_PROCESSING, processing as f64);
+    Ok(())
+}
+
+/// Fails if some migrations were not applied to the database.
+///
+/// The migrations depend on another version of SeaORM than the API, therefore they
+/// are checked with a dedicated connection, closed right after. The check is skipped
+/// once it succeeded.
+async fn check_migrations() -> Result<(), String> {
+    if MIGRATED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let url = database_url().await.map_err(|e| e.to_string())?;
+    let db = migration::sea_orm::Database::connect(url)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pending = Migrator::get_pending_migrations(&db).await;
+    if let Err(e) = db.close().await {
+        warn!("cannot close the migration connection: {e}");
+    }
+    let pending = pending.map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        MIGRATED.store(true, Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err(format!(
+            "{} pending migrations: {}",
+            pending.len(),
+            pending
+                .iter()
+                .map(|m| m.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+/// Runs the check of a dependency, measuring its latency.
+async fn check<T, F>(name: &str, f: F) -> (DependencyHealth, Option<T>)
+where
+    F: Future<Output = Result<T, String>>,
+{
+    let start = Instant::now();
+    let result = timeout(CHECK_TIMEOUT, f)
+        .await
+        .unwrap_or_else(|_| Err(format!("the {name} check timed out")));
+    let latency_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(value) => (
+            DependencyHealth {
+                status: HealthStatus::Up,
+                latency_ms,
+                details: None,
+            },
+            Some(value),
+        ),
+        Err(details) => (
+            DependencyHealth {
+                status: HealthStatus::Down,
+                latency_ms,
+                details: Some(details),
+            },
+            None,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check() {
+        let (health, value) = check("database", async { Ok::<_, String>(1) }).await;
+        assert_eq!(health.status, HealthStatus::Up);
+        assert_eq!(value, Some(1));
+
+        let (health, value) = check::<(), _>("migrations", async {
+            Err(String::from(
+                "1 pending migrations: m20261019_120000_submission_review",
+            ))
+        })
+        .await;
+        assert_eq!(health.status, HealthStatus::Down);
+        assert!(health.details.unwrap().starts_with("1 pending migrations"));
+        assert!(value.is_none());
+    }
+}
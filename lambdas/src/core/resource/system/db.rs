@@ -0,0 +1,21 @@
+use entity::{
+    bna_pipeline, submission,
+    wrappers::{ApprovalStatus, BNAPipelineStatus},
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter};
+
+/// Counts the submissions waiting for a review.
+pub(crate) async fn count_pending_submissions(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    submission::Entity::find()
+        .filter(submission::Column::Status.eq(ApprovalStatus::Pending.to_string()))
+        .count(db)
+        .await
+}
+
+/// Counts the pipelines being processed.
+pub(crate) async fn count_processing_pipelines(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    bna_pipeline::Entity::find()
+        .filter(bna_pipeline::Column::Status.eq(BNAPipelineStatus::Processing.to_string()))
+        .count(db)
+        .await
+}
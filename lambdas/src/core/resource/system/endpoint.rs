@@ -1,9 +1,18 @@
+use super::{
+    adaptor::{get_liveness_adaptor, get_metrics_adaptor, get_readiness_adaptor},
+    schema::{Health, HealthStatus},
+};
+use crate::core::extract::Json;
+use axum::http::{header, StatusCode};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 const TAG: &str = "system";
 
 pub fn routes() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(get_health))
+    OpenApiRouter::new()
+        .routes(routes!(get_health))
+        .routes(routes!(get_health_live))
+        .routes(routes!(get_health_ready))
 }
 
 #[utoipa::path(
@@ -17,3 +26,43 @@ pub fn routes() -> OpenApiRouter {
 async fn get_health() -> &'static str {
     "OK"
 }
+
+#[utoipa::path(
+  get,
+  path = "/health/live",
+  description = "Get the liveness of the system, without checking its dependencies.",
+  tag = TAG,
+  responses(
+    (status = OK, description = "The system is alive", body = Health),
+  ))]
+async fn get_health_live() -> Json<Health> {
+    Json(get_liveness_adaptor())
+}
+
+#[utoipa::path(
+  get,
+  path = "/health/ready",
+  description = "Get the readiness of the system, i.e. the status of the database, of its migrations and of the analysis queue.",
+  tag = TAG,
+  responses(
+    (status = OK, description = "The system is ready to serve requests", body = Health),
+    (status = SERVICE_UNAVAILABLE, description = "A dependency of the system is unavailable", body = Health),
+  ))]
+async fn get_health_ready() -> (StatusCode, Json<Health>) {
+    let health = get_readiness_adaptor().await;
+    let status = match health.status {
+        HealthStatus::Up => StatusCode::OK,
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(health))
+}
+
+/// Get the metrics of the system in the Prometheus text format.
+///
+/// Only served in standalone mode, the Lambda mode emitting them as logs instead.
+pub async fn get_metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        get_metrics_adaptor().await,
+    )
+}
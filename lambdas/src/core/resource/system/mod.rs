@@ -1 +1,5 @@
+//! Module for the /health endpoints.
+pub mod adaptor;
+mod db;
 pub mod endpoint;
+mod schema;
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Version of the API.
+pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Git commit the API was built from, provided at compile time.
+pub(crate) const GIT_SHA: Option<&str> = option_env!("BNA_API_GIT_SHA");
+
+#[derive(ToSchema, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[schema(description = "Status of the API or of one of its dependencies")]
+pub(crate) enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(description = "Health of the API")]
+pub(crate) struct Health {
+    /// Overall status.
+    pub(crate) status: HealthStatus,
+    /// Version of the API.
+    #[schema(examples("1.1.0"))]
+    pub(crate) version: String,
+    /// Git commit the API was built from.
+    #[schema(examples("0e9edba"))]
+    pub(crate) git_sha: Option<String>,
+    /// Health of the dependencies, keyed by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub(crate) checks: BTreeMap<String, DependencyHealth>,
+}
+
+impl Health {
+    /// Creates the health of the API from the health of its dependencies.
+    ///
+    /// The API is up only if all its dependencies are up.
+    pub(crate) fn new(checks: BTreeMap<String, DependencyHealth>) -> Self {
+        let status = if checks.values().all(|c| c.status == HealthStatus::Up) {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+        Self {
+            status,
+            version: VERSION.to_string(),
+            git_sha: GIT_SHA.map(String::from),
+            checks,
+        }
+    }
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+#[schema(description = "Health of a dependency of the API")]
+pub(crate) struct DependencyHealth {
+    /// Status of the dependency.
+    pub(crate) status: HealthStatus,
+    /// Time taken to check the dependency, in milliseconds.
+    #[schema(examples(3))]
+    pub(crate) latency_ms: u64,
+    /// Explanation of the status, if any.
+    #[schema(examples(
+        "2 pending migrations: m20261019_110000_webhook, m20261019_120000_submission_review"
+    ))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) details: Option<String>,
+}
@@ -1,16 +1,16 @@
 use crate::{
-    core::resource::{
-        schema::{ErrorResponses, PaginationParameters},
-        usstates::{
-            adaptor::{get_us_state_adaptor, get_us_states_adaptor},
-            schema::{UsState, UsStates},
+    core::{
+        extract::{Json, Path, Query},
+        metrics,
+        resource::{
+            schema::{ErrorResponses, PaginationParameters},
+            usstates::{
+                adaptor::{get_us_state_adaptor, get_us_states_adaptor},
+                schema::{UsState, UsStates},
+            },
         },
     },
-    database_connect_or_init, Context, ExecutionError, PageFlow, Paginatron,
-};
-use axum::{
-    extract::{Path, Query},
-    Json,
+    database_read_connect_or_init, Context, ExecutionError, PageFlow, Paginatron,
 };
 use utoipa_axum::{router::OpenApiRouter, routes};
 
@@ -38,8 +38,8 @@ async fn get_us_state(
     Path(name): Path<String>,
     ctx: Context,
 ) -> Result<Json<UsState>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    get_us_state_adaptor(db, &name, ctx)
+    let db = &database_read_connect_or_init().await?;
+    metrics::adaptor("get_us_state_adaptor", get_us_state_adaptor(db, &name, ctx))
         .await
         .map(UsState::from)
         .map(Json)
@@ -60,8 +60,12 @@ async fn get_us_state(
 async fn get_us_states(
     Query(pagination): Query<PaginationParameters>,
 ) -> Result<PageFlow<UsStates>, ExecutionError> {
-    let db = database_connect_or_init().await?;
-    let payload = get_us_states_adaptor(db, pagination.page(), pagination.page_size()).await?;
+    let db = &database_read_connect_or_init().await?;
+    let payload = metrics::adaptor(
+        "get_us_states_adaptor",
+        get_us_states_adaptor(db, pagination.page(), pagination.page_size()),
+    )
+    .await?;
     Ok(PageFlow::new(
         Paginatron::new(None, payload.0, pagination.page(), pagination.page_size()),
         payload.1.into(),
@@ -0,0 +1,167 @@
+use super::db::{delete_webhook, fetch_webhook, fetch_webhooks};
+use crate::{
+    core::validation::{is_public_https_url, Validator},
+    Context, ExecutionError,
+};
+use entity::{
+    webhook_subscription,
+    wrappers::{
+        webhook_subscription::{WebhookSubscriptionPatch, WebhookSubscriptionPost},
+        WebhookEvent,
+    },
+};
+use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, IntoActiveModel};
+use tracing::info;
+use uuid::Uuid;
+
+pub async fn get_webhook_adaptor(
+    db: &DatabaseConnection,
+    webhook_id: Uuid,
+    ctx: Context,
+) -> Result<webhook_subscription::Model, ExecutionError> {
+    authorize(&ctx)?;
+
+    // Fetch the model.
+    let model = fetch_webhook(db, webhook_id).await?;
+    match model {
+        Some(model) => Ok(model),
+        None => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find a webhook with the ID {webhook_id}"),
+        )),
+    }
+}
+
+pub async fn get_webhooks_adaptor(
+    db: &DatabaseConnection,
+    page: u64,
+    page_size: u64,
+    ctx: Context,
+) -> Result<(u64, Vec<webhook_subscription::Model>), ExecutionError> {
+    authorize(&ctx)?;
+
+    // Fetch a page of webhooks.
+    Ok(fetch_webhooks(db, page, page_size).await?)
+}
+
+pub async fn post_webhook_adaptor(
+    db: &DatabaseConnection,
+    webhook: WebhookSubscriptionPost,
+    ctx: Context,
+) -> Result<webhook_subscription::Model, ExecutionError> {
+    authorize(&ctx)?;
+
+    // Validate the subscription.
+    validate_webhook(Some(&webhook.url), Some(&webhook.events), &ctx)?;
+
+    // Generate a secret if none was provided.
+    let mut webhook = webhook;
+    if webhook.secret.is_none() {
+        webhook.secret = Some(generate_secret());
+    }
+
+    // Turn the wrapper into an active model.
+    let mut active_model = webhook.into_active_model();
+    active_model.id = ActiveValue::Set(Uuid::new_v4());
+
+    // And insert a new entry.
+    info!(
+        "inserting webhook subscription into database: {:?}",
+        active_model.url
+    );
+    let model = active_model.insert(db).await?;
+    Ok(model)
+}
+
+pub async fn patch_webhook_adaptor(
+    db: &DatabaseConnection,
+    webhook_id: Uuid,
+    webhook: WebhookSubscriptionPatch,
+    ctx: Context,
+) -> Result<webhook_subscription::Model, ExecutionError> {
+    authorize(&ctx)?;
+
+    // Validate the changes.
+    validate_webhook(webhook.url.as_deref(), webhook.events.as_deref(), &ctx)?;
+
+    // Ensure the webhook exists.
+    get_webhook_adaptor(db, webhook_id, ctx).await?;
+
+    // Turn the wrapper into an active model.
+    let mut active_model = webhook.into_active_model();
+    active_model.id = ActiveValue::Unchanged(webhook_id);
+
+    // Update the entry.
+    let model = active_model.update(db).await?;
+    Ok(model)
+}
+
+/// Ensures the caller is an administrator.
+///
+/// The subscriptions receive the signed events, therefore only the administrators
+/// can manage them.
+fn authorize(ctx: &Context) -> Result<(), ExecutionError> {
+    if ctx.principal().is_none_or(|p| p.trim().is_empty()) {
+        return Err(ExecutionError::Unauthorized(
+            ctx.request_id(),
+            ctx.source(),
+            "the caller must be authenticated to manage the webhooks".to_string(),
+        ));
+    }
+    if !ctx.is_admin() {
+        return Err(ExecutionError::Forbidden(
+            ctx.request_id(),
+            ctx.source(),
+            "only the administrators can manage the webhooks".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates the attributes of a webhook, reporting all the invalid ones at once.
+///
+/// The events are delivered to public HTTPS endpoints only, so that a subscription
+/// cannot be used to reach the internal services.
+fn validate_webhook(
+    url: Option<&str>,
+    events: Option<&[WebhookEvent]>,
+    ctx: &Context,
+) -> Result<(), ExecutionError> {
+    let mut validator = Validator::new(ctx.request_id());
+    validator
+        .check(
+            url.is_none_or(is_public_https_url),
+            "/url",
+            "the URL must use HTTPS and point to a public host",
+        )
+        .check(
+            events.is_none_or(|events| !events.is_empty()),
+            "/events",
+            "at least one event is required",
+        );
+    validator.finish()
+}
+
+pub async fn delete_webhook_adaptor(
+    db: &DatabaseConnection,
+    webhook_id: Uuid,
+    ctx: Context,
+) -> Result<(), ExecutionError> {
+    authorize(&ctx)?;
+
+    let res = delete_webhook(db, webhook_id).await?;
+    match res.rows_affected {
+        0 => Err(ExecutionError::NotFound(
+            ctx.request_id(),
+            ctx.source(),
+            format!("cannot find a webhook with the ID {webhook_id}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Generates a random secret to sign the deliveries.
+fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
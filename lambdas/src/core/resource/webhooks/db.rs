@@ -0,0 +1,37 @@
+use entity::webhook_subscription;
+use sea_orm::{DatabaseConnection, DeleteResult, EntityTrait, PaginatorTrait, QueryOrder};
+use uuid::Uuid;
+
+pub async fn fetch_webhook(
+    db: &DatabaseConnection,
+    webhook_id: Uuid,
+) -> Result<Option<webhook_subscription::Model>, sea_orm::DbErr> {
+    webhook_subscription::Entity::find_by_id(webhook_id)
+        .one(db)
+        .await
+}
+
+pub async fn fetch_webhooks(
+    db: &DatabaseConnection,
+    page: u64,
+    page_size: u64,
+) -> Result<(u64, Vec<webhook_subscription::Model>), sea_orm::DbErr> {
+    let select =
+        webhook_subscription::Entity::find().order_by_asc(webhook_subscription::Column::CreatedAt);
+    let models = select
+        .clone()
+        .paginate(db, page_size)
+        .fetch_page(page)
+        .await?;
+    let count = select.count(db).await?;
+    Ok((count, models))
+}
+
+pub async fn delete_webhook(
+    db: &DatabaseConnection,
+    webhook_id: Uuid,
+) -> Result<DeleteResult, sea_orm::DbErr> {
+    webhook_subscription::Entity::delete_by_id(webhook_id)
+        .exec(db)
+        .await
+}
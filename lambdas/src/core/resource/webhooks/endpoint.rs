@@ -0,0 +1,172 @@
+use super::{
+    adaptor::{
+        delete_webhook_adaptor, get_webhook_adaptor, get_webhooks_adaptor, patch_webhook_adaptor,
+        post_webhook_adaptor,
+    },
+    schema::{Webhook, WebhookPatch, WebhookPost, Webhooks},
+};
+use crate::{
+    core::{
+        extract::{Json, Path, Query},
+        metrics,
+        resource::schema::{ErrorResponses, PaginationParameters},
+    },
+    database_connect_or_init, database_read_connect_or_init, Context, ExecutionError, PageFlow,
+    Paginatron,
+};
+use axum::http::StatusCode;
+use entity::wrappers::webhook_subscription::{WebhookSubscriptionPatch, WebhookSubscriptionPost};
+use tracing::debug;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+const TAG: &str = "webhook";
+
+pub fn routes() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_webhook))
+        .routes(routes!(get_webhooks))
+        .routes(routes!(post_webhook))
+        .routes(routes!(patch_webhook))
+        .routes(routes!(delete_webhook))
+}
+
+#[utoipa::path(
+  get,
+  path = "/admin/webhooks/{webhook_id}",
+  description = "Get the details of a specific webhook",
+  tag = TAG,
+  params(
+    ("webhook_id" = Uuid, Path, description = "Webhook identifier")
+  ),
+  responses(
+    (status = OK, description = "Fetches the details of a webhook", body = Webhook),
+    ErrorResponses,
+  ))]
+async fn get_webhook(
+    Path(webhook_id): Path<Uuid>,
+    ctx: Context,
+) -> Result<Json<Webhook>, ExecutionError> {
+    let db = &database_read_connect_or_init().await?;
+    metrics::adaptor(
+        "get_webhook_adaptor",
+        get_webhook_adaptor(db, webhook_id, ctx),
+    )
+    .await
+    .map(Webhook::from)
+    .map(Json)
+}
+
+#[utoipa::path(
+  get,
+  path = "/admin/webhooks",
+  description = "Get the details of all webhooks",
+  tag = TAG,
+  params(
+    PaginationParameters,
+  ),
+  responses(
+    (status = OK, description = "Fetches the details of all webhooks", body = Webhooks),
+    ErrorResponses,
+  ))]
+async fn get_webhooks(
+    Query(pagination): Query<PaginationParameters>,
+    ctx: Context,
+) -> Result<PageFlow<Webhooks>, ExecutionError> {
+    let db = &database_read_connect_or_init().await?;
+    let (total_items, models) = metrics::adaptor(
+        "get_webhooks_adaptor",
+        get_webhooks_adaptor(db, pagination.page(), pagination.page_size(), ctx),
+    )
+    .await?;
+    let payload = Webhooks(models.into_iter().map(Webhook::from).collect());
+    Ok(PageFlow::new(
+        Paginatron::new(None, total_items, pagination.page(), pagination.page_size()),
+        payload,
+    ))
+}
+
+#[utoipa::path(
+  post,
+  path = "/admin/webhooks",
+  description = "Create a new webhook. The response is the only one containing the secret used to sign the deliveries.",
+  tag = TAG,
+  request_body = WebhookPost,
+  responses(
+    (status = CREATED, description = "Creates a new webhook", body = Webhook),
+    ErrorResponses,
+  ))]
+async fn post_webhook(
+    ctx: Context,
+    Json(webhook): Json<WebhookSubscriptionPost>,
+) -> Result<(StatusCode, Json<Webhook>), ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "post_webhook_adaptor",
+        post_webhook_adaptor(db, webhook, ctx),
+    )
+    .await
+    .map_err(|e| {
+        debug!("{e}");
+        e
+    })
+    .map(Webhook::with_secret)
+    .map(|v| (StatusCode::CREATED, Json(v)))
+}
+
+#[utoipa::path(
+  patch,
+  path = "/admin/webhooks/{webhook_id}",
+  description = "Update a specific webhook",
+  tag = TAG,
+  request_body = WebhookPatch,
+  params(
+    ("webhook_id" = Uuid, Path, description = "Webhook identifier")
+  ),
+  responses(
+    (status = OK, description = "Updates a webhook", body = Webhook),
+    ErrorResponses,
+  ))]
+async fn patch_webhook(
+    Path(webhook_id): Path<Uuid>,
+    ctx: Context,
+    Json(webhook): Json<WebhookSubscriptionPatch>,
+) -> Result<Json<Webhook>, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "patch_webhook_adaptor",
+        patch_webhook_adaptor(db, webhook_id, webhook, ctx),
+    )
+    .await
+    .map_err(|e| {
+        debug!("{e}");
+        e
+    })
+    .map(Webhook::from)
+    .map(Json)
+}
+
+#[utoipa::path(
+  delete,
+  path = "/admin/webhooks/{webhook_id}",
+  description = "Delete a specific webhook and its pending deliveries",
+  tag = TAG,
+  params(
+    ("webhook_id" = Uuid, Path, description = "Webhook identifier")
+  ),
+  responses(
+    (status = NO_CONTENT, description = "Deletes a webhook"),
+    ErrorResponses,
+  ))]
+async fn delete_webhook(
+    Path(webhook_id): Path<Uuid>,
+    ctx: Context,
+) -> Result<StatusCode, ExecutionError> {
+    let db = &database_connect_or_init().await?;
+    metrics::adaptor(
+        "delete_webhook_adaptor",
+        delete_webhook_adaptor(db, webhook_id, ctx),
+    )
+    .await
+    .map(|_| StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,5 @@
+//! Module for the /admin/webhooks enpoint.
+pub mod adaptor;
+mod db;
+pub mod endpoint;
+mod schema;
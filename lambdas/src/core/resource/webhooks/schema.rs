@@ -0,0 +1,97 @@
+//! Describes the Webhook schemas.
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(ToSchema, Serialize, Deserialize)]
+pub(crate) enum WebhookEvent {
+    #[serde(rename = "pipeline.completed")]
+    PipelineCompleted,
+    #[serde(rename = "pipeline.failed")]
+    PipelineFailed,
+    #[serde(rename = "rating.created")]
+    RatingCreated,
+    #[serde(rename = "submission.status_changed")]
+    SubmissionStatusChanged,
+}
+
+#[derive(ToSchema, Serialize, Deserialize)]
+pub(crate) struct Webhook {
+    /// Webhook identifier
+    id: Uuid,
+    /// URL receiving the events
+    #[schema(examples("https://example.com/bna/hooks"))]
+    url: String,
+    /// Secret used to sign the deliveries
+    ///
+    /// It is only returned when the webhook is created. Each delivery has an
+    /// `X-BNA-Signature` header containing `sha256=` followed by the hex encoded
+    /// HMAC-SHA256 of the body computed with this secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+    /// Events the webhook is subscribed to
+    events: Vec<WebhookEvent>,
+    /// Whether the events are delivered
+    active: bool,
+    /// Creation date
+    created_at: DateTime<chrono::FixedOffset>,
+    /// Update date
+    updated_at: Option<DateTime<chrono::FixedOffset>>,
+}
+
+impl Webhook {
+    /// Creates a webhook exposing its secret.
+    pub(crate) fn with_secret(value: entity::webhook_subscription::Model) -> Self {
+        let secret = value.secret.clone();
+        Self {
+            secret: Some(secret),
+            ..Self::from(value)
+        }
+    }
+}
+
+impl From<entity::webhook_subscription::Model> for Webhook {
+    fn from(value: entity::webhook_subscription::Model) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            secret: None,
+            events: serde_json::from_value(value.events).unwrap_or_default(),
+            active: value.active,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+#[derive(ToSchema, Serialize)]
+pub(crate) struct Webhooks(pub(crate) Vec<Webhook>);
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub(crate) struct WebhookPost {
+    /// URL receiving the events
+    #[schema(examples("https://example.com/bna/hooks"))]
+    url: String,
+    /// Secret used to sign the deliveries, generated if not provided
+    secret: Option<String>,
+    /// Events the webhook is subscribed to
+    events: Vec<WebhookEvent>,
+    /// Whether the events are delivered, `true` by default
+    active: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub(crate) struct WebhookPatch {
+    /// URL receiving the events
+    #[schema(examples("https://example.com/bna/hooks"))]
+    url: Option<String>,
+    /// Secret used to sign the deliveries
+    secret: Option<String>,
+    /// Events the webhook is subscribed to
+    events: Option<Vec<WebhookEvent>>,
+    /// Whether the events are delivered
+    active: Option<bool>,
+}
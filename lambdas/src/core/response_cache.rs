@@ -0,0 +1,300 @@
+//! In-process cache of the expensive aggregate queries.
+//!
+//! The results of the heavy joins, e.g. the reports or the top cities, are kept in
+//! memory for `cache.ttl` seconds, keyed by their route and normalized query. The
+//! cache lives as long as the process, therefore across the invocations of a warm
+//! Lambda as well as in standalone mode. It is cleared whenever a city or a rating is
+//! written through the API.
+//!
+//! The invalidation is per process only: the other Lambda instances, and the other
+//! standalone servers, keep serving their entries until they expire. A write may
+//! therefore take up to `cache.ttl` seconds to be visible everywhere.
+//!
+//! The lookups are counted in the metrics and reported to the client with the
+//! `Cache-Status` header defined by RFC 9211.
+use crate::core::{config::config, metrics};
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Header reporting how the cache handled the request.
+pub const CACHE_STATUS_HEADER: HeaderName = HeaderName::from_static("cache-status");
+
+/// Name of the cache in the `Cache-Status` header.
+const CACHE_NAME: &str = "bna-api";
+
+/// Global static response cache.
+static CACHE: Lazy<ResponseCache> = Lazy::new(ResponseCache::default);
+
+tokio::task_local! {
+    /// Outcome of the cache lookup of the request being processed.
+    static CURRENT_STATUS: Arc<Mutex<Option<CacheStatus>>>;
+}
+
+/// Outcome of a cache lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// The entry was served from the cache, with its remaining time to live.
+    Hit(Duration),
+    /// The entry was fetched, then stored in the cache.
+    Miss,
+    /// The cache is disabled.
+    Bypass,
+}
+
+impl CacheStatus {
+    /// Formats the status as the value of the `Cache-Status` header.
+    pub fn header_value(&self) -> String {
+        match self {
+            CacheStatus::Hit(ttl) => format!("{CACHE_NAME}; hit; ttl={}", ttl.as_secs()),
+            CacheStatus::Miss => format!("{CACHE_NAME}; fwd=miss; stored"),
+            CacheStatus::Bypass => format!("{CACHE_NAME}; fwd=bypass"),
+        }
+    }
+}
+
+/// Cached value, shared with the callers.
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires_at: Instant,
+}
+
+/// TTL cache of values of any type.
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Incremented on every invalidation, to discard the values fetched meanwhile.
+    generation: AtomicU64,
+}
+
+impl ResponseCache {
+    /// Returns the cached value of the key, or fetches and stores it.
+    ///
+    /// The errors are never cached.
+    pub async fn get_or_fetch<T, E, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        max_entries: usize,
+        fetch: F,
+    ) -> Result<(T, CacheStatus), E>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if ttl.is_zero() || max_entries == 0 {
+            return fetch().await.map(|value| (value, CacheStatus::Bypass));
+        }
+        if let Some((value, remaining)) = self.get::<T>(key) {
+            return Ok((value, CacheStatus::Hit(remaining)));
+        }
+        let generation = self.generation.load(Ordering::Acquire);
+        let value = fetch().await?;
+        let mut entries = self.lock();
+        if generation == self.generation.load(Ordering::Acquire) {
+            let now = Instant::now();
+            if entries.len() >= max_entries {
+                entries.retain(|_, entry| entry.expires_at > now);
+            }
+            if entries.len() >= max_entries {
+                // Evict the entry closest to its expiration.
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| key.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+            entries.insert(
+                key.to_string(),
+                Entry {
+                    value: Arc::new(value.clone()),
+                    expires_at: now + ttl,
+                },
+            );
+        }
+        Ok((value, CacheStatus::Miss))
+    }
+
+    /// Returns the value of a key which has not expired yet, with its time to live.
+    fn get<T>(&self, key: &str) -> Option<(T, Duration)>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let entries = self.lock();
+        let entry = entries.get(key)?;
+        let remaining = entry.expires_at.checked_duration_since(Instant::now())?;
+        let value = entry.value.downcast_ref::<T>()?;
+        Some((value.clone(), remaining))
+    }
+
+    /// Removes all the entries.
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().expect("a healthy lock")
+    }
+}
+
+/// Returns the cached result of an aggregate query, or runs it.
+///
+/// The `name` identifies the query in the metrics, and the `key` identifies its
+/// parameters, i.e. its route and normalized query.
+pub async fn cached<T, E, F, Fut>(name: &'static str, key: &str, fetch: F) -> Result<T, E>
+where
+    T: Clone + Send + Sync + 'static,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let settings = &config().cache;
+    let (value, status) = CACHE
+        .get_or_fetch(
+            key,
+            Duration::from_secs(settings.ttl),
+            settings.max_entries,
+            fetch,
+        )
+        .await?;
+    match status {
+        CacheStatus::Hit(_) => metrics::observe_cache_lookup(name, true),
+        CacheStatus::Miss => metrics::observe_cache_lookup(name, false),
+        CacheStatus::Bypass => {}
+    }
+    let _ = CURRENT_STATUS.try_with(|current| {
+        *current.lock().expect("a healthy lock") = Some(status);
+    });
+    Ok(value)
+}
+
+/// Clears the cache, after a write changing the results of the cached queries.
+///
+/// Only the cache of the current process is cleared.
+pub fn invalidate() {
+    CACHE.clear();
+}
+
+/// Middleware reporting the outcome of the cache lookup in the `Cache-Status` header.
+pub async fn track_cache_status(request: Request, next: Next) -> Response {
+    let current = Arc::new(Mutex::new(None));
+    let mut response = CURRENT_STATUS
+        .scope(current.clone(), next.run(request))
+        .await;
+    let status = *current.lock().expect("a healthy lock");
+    if let Some(status) = status {
+        if let Ok(value) = HeaderValue::from_str(&status.header_value()) {
+            response.headers_mut().insert(CACHE_STATUS_HEADER, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn test_get_or_fetch() {
+        let cache = ResponseCache::default();
+        let (value, status) = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Ok::<_, Infallible>(1) })
+            .await
+            .unwrap();
+        assert_eq!((value, status), (1, CacheStatus::Miss));
+
+        let (value, status) = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Ok::<_, Infallible>(2) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+        assert!(matches!(status, CacheStatus::Hit(_)));
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = ResponseCache::default();
+        let result = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Err::<u32, _>("down") })
+            .await;
+        assert!(result.is_err());
+        let (_, status) = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Ok::<_, Infallible>(1) })
+            .await
+            .unwrap();
+        assert_eq!(status, CacheStatus::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_clear() {
+        let cache = ResponseCache::default();
+        let _ = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Ok::<_, Infallible>(1) })
+            .await;
+        cache.clear();
+        let (value, status) = cache
+            .get_or_fetch("/reports", TTL, 10, || async { Ok::<_, Infallible>(2) })
+            .await
+            .unwrap();
+        assert_eq!((value, status), (2, CacheStatus::Miss));
+    }
+
+    #[tokio::test]
+    async fn test_max_entries() {
+        let cache = ResponseCache::default();
+        for year in 2020..2025 {
+            let _ = cache
+                .get_or_fetch(&format!("/reports/{year}"), TTL, 2, || async {
+                    Ok::<_, Infallible>(year)
+                })
+                .await;
+        }
+        assert_eq!(cache.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache() {
+        let cache = ResponseCache::default();
+        let (_, status) = cache
+            .get_or_fetch("/reports", Duration::ZERO, 10, || async {
+                Ok::<_, Infallible>(1)
+            })
+            .await
+            .unwrap();
+        assert_eq!(status, CacheStatus::Bypass);
+        assert!(cache.lock().is_empty());
+    }
+
+    #[test]
+    fn test_header_value() {
+        assert_eq!(
+            CacheStatus::Hit(Duration::from_secs(42)).header_value(),
+            "bna-api; hit; ttl=42"
+        );
+        assert_eq!(
+            CacheStatus::Miss.header_value(),
+            "bna-api; fwd=miss; stored"
+        );
+    }
+}
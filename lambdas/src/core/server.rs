@@ -0,0 +1,233 @@
+//! Standalone HTTP server.
+//!
+//! Outside of AWS Lambda, the API listens on the `server.bind_address`, either a TCP
+//! address or a Unix socket, and is served over TLS if a certificate and its key are
+//! configured.
+//!
+//! On `SIGTERM` or `SIGINT`, the server stops accepting connections and lets the
+//! in-flight requests complete for at most `server.shutdown_timeout` seconds, before
+//! closing the remaining connections.
+use crate::core::config::{ConfigError, ListenAddress, ServerConfig};
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Address, Handle};
+use std::{io, net::TcpListener, path::PathBuf, time::Duration};
+use tracing::{info, warn};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    /// Another process is already listening on the address.
+    #[error("cannot listen on {0}: the address is already in use, is another server running?")]
+    AddressInUse(ListenAddress),
+
+    /// The address cannot be bound.
+    #[error("cannot listen on {0}: {1}")]
+    Bind(ListenAddress, io::Error),
+
+    /// The TLS certificate or its key cannot be loaded.
+    #[error("cannot load the TLS certificate {0} and key {1}: {2}")]
+    Tls(PathBuf, PathBuf, io::Error),
+
+    /// The server failed while serving the requests.
+    #[error("server error: {0}")]
+    Serve(io::Error),
+
+    /// The configuration of the server is invalid.
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+/// Serves the app until a termination signal is received.
+pub async fn serve(app: Router, config: &ServerConfig) -> Result<(), ServerError> {
+    let address = config.listen_address()?;
+    let tls = match config.tls() {
+        Some((cert, key)) => Some(
+            RustlsConfig::from_pem_file(cert, key)
+                .await
+                .map_err(|e| ServerError::Tls(cert.to_path_buf(), key.to_path_buf(), e))?,
+        ),
+        None => None,
+    };
+    let timeout = Duration::from_secs(config.shutdown_timeout);
+    let app = app.into_make_service();
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    let result = match &address {
+        ListenAddress::Tcp(socket) => {
+            let listener = bind_tcp(&address, *socket)?;
+            info!("listening on {scheme}://{address}");
+            let handle = shutdown_handle(timeout);
+            match tls {
+                Some(tls) => {
+                    axum_server::from_tcp_rustls(listener, tls)
+                        .map_err(ServerError::Serve)?
+                        .handle(handle)
+                        .serve(app)
+                        .await
+                }
+                None => {
+                    axum_server::from_tcp(listener)
+                        .map_err(ServerError::Serve)?
+                        .handle(handle)
+                        .serve(app)
+                        .await
+                }
+            }
+        }
+        #[cfg(unix)]
+        ListenAddress::Unix(path) => {
+            let listener = bind_unix(&address, path)?;
+            info!("listening on {scheme} over {address}");
+            let handle = shutdown_handle(timeout);
+            let result = match tls {
+                Some(tls) => {
+                    axum_server::from_unix_rustls(listener, tls)
+                        .map_err(ServerError::Serve)?
+                        .handle(handle)
+                        .serve(app)
+                        .await
+                }
+                None => {
+                    axum_server::from_unix(listener)
+                        .map_err(ServerError::Serve)?
+                        .handle(handle)
+                        .serve(app)
+                        .await
+                }
+            };
+            let _ = std::fs::remove_file(path);
+            result
+        }
+        #[cfg(not(unix))]
+        ListenAddress::Unix(_) => {
+            return Err(ServerError::Bind(
+                address.clone(),
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                ),
+            ))
+        }
+    };
+    info!("server stopped");
+    result.map_err(ServerError::Serve)
+}
+
+/// Binds a TCP address.
+fn bind_tcp(
+    address: &ListenAddress,
+    socket: std::net::SocketAddr,
+) -> Result<TcpListener, ServerError> {
+    let listener = TcpListener::bind(socket).map_err(|e| bind_error(address, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| bind_error(address, e))?;
+    Ok(listener)
+}
+
+/// Binds a Unix socket, replacing the socket left over by a previous server.
+#[cfg(unix)]
+fn bind_unix(
+    address: &ListenAddress,
+    path: &std::path::Path,
+) -> Result<std::os::unix::net::UnixListener, ServerError> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        match UnixStream::connect(path) {
+            Ok(_) => return Err(ServerError::AddressInUse(address.clone())),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path).map_err(|e| bind_error(address, e))?;
+            }
+            Err(_) => {}
+        }
+    }
+    let listener = UnixListener::bind(path).map_err(|e| bind_error(address, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| bind_error(address, e))?;
+    Ok(listener)
+}
+
+fn bind_error(address: &ListenAddress, error: io::Error) -> ServerError {
+    match error.kind() {
+        io::ErrorKind::AddrInUse => ServerError::AddressInUse(address.clone()),
+        _ => ServerError::Bind(address.clone(), error),
+    }
+}
+
+/// Creates a server handle, shut down gracefully on the termination signals.
+fn shutdown_handle<A>(timeout: Duration) -> Handle<A>
+where
+    A: Address + Send + Sync + 'static,
+{
+    let handle = Handle::new();
+    let shutdown = handle.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!(
+            "shutting down, waiting {}s for the in-flight requests",
+            timeout.as_secs()
+        );
+        shutdown.graceful_shutdown(Some(timeout));
+    });
+    handle
+}
+
+/// Completes when `SIGTERM` or `SIGINT` is received.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("cannot listen for SIGINT: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("cannot listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_in_use() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = taken.local_addr().unwrap();
+        let result = bind_tcp(&ListenAddress::Tcp(socket), socket);
+        assert!(matches!(result, Err(ServerError::AddressInUse(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_unix_socket_is_replaced() {
+        let path = std::env::temp_dir().join(format!("bna-api-{}.sock", std::process::id()));
+        let address = ListenAddress::Unix(path.clone());
+        let first = bind_unix(&address, &path).unwrap();
+        assert!(matches!(
+            bind_unix(&address, &path),
+            Err(ServerError::AddressInUse(_))
+        ));
+        drop(first);
+        assert!(bind_unix(&address, &path).is_ok());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,268 @@
+//! Distributed tracing of the API with OpenTelemetry.
+//!
+//! A span is recorded for each request and for each database query. The spans are
+//! exported with OTLP to the collector configured by the standard
+//! `OTEL_EXPORTER_OTLP_*` environment variables, or printed on the standard output for
+//! local development. The exporter is selected with `log.traces_exporter`, i.e. the
+//! `BNA_API_TRACES_EXPORTER` environment variable: `otlp`, `stdout` or `none` (the
+//! default).
+//!
+//! The W3C `traceparent` header of the incoming requests is honored, so that the spans
+//! of the API join the trace of the caller.
+//!
+//! Each request is identified by the `X-Request-Id` header, which is echoed in the
+//! response. In Lambda mode, the id provided by API Gateway takes precedence over the
+//! one sent by the client, so that the responses match the access logs. Otherwise the
+//! id of the client is kept, or a new one is generated.
+use crate::core::config::{LogConfig, LogFormat, TracesExporter};
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{request::Parts, HeaderMap, HeaderName, HeaderValue, Request, Response},
+    middleware::Next,
+};
+use lambda_http::Error;
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::{Span as _, SpanKind, Status, Tracer, TracerProvider},
+    KeyValue,
+};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use std::time::{Duration, SystemTime};
+use tower_http::{
+    request_id::{MakeRequestId, RequestId},
+    trace::{DefaultOnResponse, OnResponse},
+};
+use tracing::{field, Level, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+};
+use uuid::Uuid;
+
+/// Name of the service in the traces.
+pub const SERVICE_NAME: &str = "bna-api";
+/// Header identifying a request.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Initializes the logs and, if an exporter is configured, the traces.
+///
+/// The logs are printed as text or as JSON, depending on the configured format.
+///
+/// The returned provider must be shut down before exiting to flush the pending spans.
+pub fn init(log: &LogConfig) -> Result<Option<SdkTracerProvider>, Error> {
+    let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
+    let provider = match log.traces_exporter {
+        TracesExporter::Otlp => Some(
+            SdkTracerProvider::builder()
+                .with_batch_exporter(
+                    opentelemetry_otlp::SpanExporter::builder()
+                        .with_http()
+                        .build()?,
+                )
+                .with_resource(resource)
+                .build(),
+        ),
+        TracesExporter::Stdout => Some(
+            SdkTracerProvider::builder()
+                .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+                .with_resource(resource)
+                .build(),
+        ),
+        TracesExporter::None => None,
+    };
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let traces = provider.as_ref().map(|provider| {
+        global::set_tracer_provider(provider.clone());
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+    });
+
+    let logs = fmt::layer()
+        // disable printing the name of the module in every log line.
+        .with_target(false)
+        // disabling time is handy because CloudWatch will add the ingestion time.
+        .without_time();
+    let logs = match log.format {
+        LogFormat::Text => logs.boxed(),
+        LogFormat::Json => logs.json().boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::from_level(Level::from(log.level)))
+        .with(logs)
+        .with(traces)
+        .try_init()?;
+    Ok(provider)
+}
+
+/// Creates the span of a request, child of the trace of the caller if any.
+pub fn make_span<B>(request: &Request<B>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(request.uri().path(), MatchedPath::as_str);
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {route}", request.method()),
+        otel.kind = "server",
+        http.request.method = %request.method(),
+        http.route = route,
+        url.path = request.uri().path(),
+        request_id,
+        http.response.status_code = field::Empty,
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    if let Err(e) = span.set_parent(parent) {
+        tracing::debug!("cannot join the trace of the caller: {e}");
+    }
+    span
+}
+
+/// Records the status of the response on the span of its request.
+#[derive(Debug, Clone, Default)]
+pub struct RecordStatus(DefaultOnResponse);
+
+impl<B> OnResponse<B> for RecordStatus {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        span.record("http.response.status_code", response.status().as_u16());
+        self.0.on_response(response, latency, span);
+    }
+}
+
+/// Records the span of a database query, as a child of the current span.
+///
+/// The query is reported once completed, therefore its span is backdated.
+pub fn record_db_query(info: &sea_orm::metric::Info<'_>) {
+    let end = SystemTime::now();
+    let start = end.checked_sub(info.elapsed).unwrap_or(end);
+    let tracer = global::tracer(SERVICE_NAME);
+    let mut span = tracer
+        .span_builder("db.query")
+        .with_kind(SpanKind::Client)
+        .with_start_time(start)
+        .with_attributes([
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.query.text", info.statement.sql.clone()),
+        ])
+        .start_with_context(&tracer, &Span::current().context());
+    if info.failed {
+        span.set_status(Status::error("the query failed"));
+    }
+    span.end_with_timestamp(end);
+}
+
+/// Adds the `traceparent` header of the current span to an outgoing request.
+pub fn inject_trace_context(headers: &mut HeaderMap) {
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+/// Returns the id given to a request by API Gateway, in Lambda mode.
+pub fn gateway_request_id(parts: &Parts) -> Option<String> {
+    match parts
+        .extensions
+        .get::<lambda_http::request::RequestContext>()
+    {
+        Some(lambda_http::request::RequestContext::ApiGatewayV2(ctx)) => ctx.request_id.clone(),
+        _ => None,
+    }
+}
+
+/// Middleware replacing the request id sent by the client with the API Gateway one.
+pub async fn prefer_gateway_request_id(request: Request<Body>, next: Next) -> Response<Body> {
+    let (mut parts, body) = request.into_parts();
+    if let Some(id) = gateway_request_id(&parts).and_then(|id| HeaderValue::from_str(&id).ok()) {
+        parts.headers.insert(REQUEST_ID_HEADER, id);
+    }
+    next.run(Request::from_parts(parts, body)).await
+}
+
+/// Identifies the requests which were neither identified by API Gateway nor by the
+/// caller, with a new id.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MakeApiRequestId;
+
+impl MakeRequestId for MakeApiRequestId {
+    fn make_request_id<B>(&mut self, _: &Request<B>) -> Option<RequestId> {
+        HeaderValue::from_str(&Uuid::new_v4().to_string())
+            .ok()
+            .map(RequestId::new)
+    }
+}
+
+/// Reads the trace context from the headers of a request.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Writes the trace context to the headers of a request.
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_request_id() {
+        let request = Request::builder().body(()).unwrap();
+        let id = MakeApiRequestId.make_request_id(&request).unwrap();
+        assert!(Uuid::parse_str(id.header_value().to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_gateway_request_id() {
+        let (mut parts, _) = Request::builder().body(()).unwrap().into_parts();
+        assert_eq!(gateway_request_id(&parts), None);
+        let mut ctx =
+            lambda_http::aws_lambda_events::apigw::ApiGatewayV2httpRequestContext::default();
+        ctx.request_id = Some(String::from("gateway-id"));
+        parts
+            .extensions
+            .insert(lambda_http::request::RequestContext::ApiGatewayV2(ctx));
+        assert_eq!(gateway_request_id(&parts).as_deref(), Some("gateway-id"));
+    }
+
+    #[test]
+    fn test_header_extractor() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            HeaderValue::from_static("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"),
+        );
+        let extractor = HeaderExtractor(&headers);
+        assert_eq!(
+            extractor.get("traceparent"),
+            Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+        );
+        assert_eq!(extractor.keys(), vec!["traceparent"]);
+    }
+}
@@ -0,0 +1,250 @@
+//! Validation of the request payloads.
+//!
+//! A [`Validator`] collects all the errors of a payload, each of them pointing to the
+//! invalid attribute, so that they can be reported at once.
+use crate::ExecutionError;
+use effortless::error::{APIError, APIErrorSource, APIErrors};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use url::{Host, Url};
+
+/// Collects the validation errors of a payload.
+#[derive(Debug, Default)]
+pub struct Validator {
+    id: Option<String>,
+    errors: Vec<APIError>,
+}
+
+impl Validator {
+    /// Creates a validator for the request `id`.
+    pub fn new(id: Option<String>) -> Self {
+        Self {
+            id,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Records an error for the attribute at `pointer` unless `condition` holds.
+    pub fn check(&mut self, condition: bool, pointer: &str, message: &str) -> &mut Self {
+        if !condition {
+            self.error(pointer, message);
+        }
+        self
+    }
+
+    /// Records an error for the attribute at `pointer`, unless one was already
+    /// recorded for it, so that each invalid attribute is reported once.
+    pub fn error(&mut self, pointer: &str, message: &str) -> &mut Self {
+        let source = APIErrorSource::Pointer(pointer.to_string());
+        if !self.errors.iter().any(|e| e.source() == Some(&source)) {
+            self.errors
+                .push(APIError::with_pointer(self.id.clone(), pointer, message));
+        }
+        self
+    }
+
+    /// Returns `true` if no error was recorded.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Consumes the validator, failing with all the recorded errors if any.
+    pub fn finish(self) -> Result<(), ExecutionError> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(ExecutionError::Validation(APIErrors::new(&self.errors)))
+        }
+    }
+}
+
+/// Returns `true` if the value contains something else than whitespaces.
+pub fn is_present(value: &str) -> bool {
+    !value.trim().is_empty()
+}
+
+/// Returns `true` if the value looks like an email address.
+///
+/// Only the overall shape is checked, i.e. `local@domain.tld` without whitespaces.
+pub fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !value.chars().any(char::is_whitespace)
+        && !domain.contains('@')
+        && domain
+            .split_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+        && !domain.ends_with('.')
+}
+
+/// Returns `true` if the value is a U.S. census FIPS place code.
+///
+/// A place code is made of the 2-digit code of the state followed by the 5-digit
+/// code of the place within the state.
+pub fn is_fips_place_code(value: &str) -> bool {
+    value.len() == 7 && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Returns `true` if the value is a valid latitude.
+pub fn is_latitude(value: f64) -> bool {
+    (-90.0..=90.0).contains(&value)
+}
+
+/// Returns `true` if the value is a valid longitude.
+pub fn is_longitude(value: f64) -> bool {
+    (-180.0..=180.0).contains(&value)
+}
+
+/// Returns `true` if the value is an HTTPS URL pointing to a public host.
+///
+/// The URLs of the loopback, link-local and private hosts are rejected, as well as the
+/// host names which can only be resolved on a private network.
+pub fn is_public_https_url(value: &str) -> bool {
+    let Ok(url) = Url::parse(value) else {
+        return false;
+    };
+    if url.scheme() != "https" {
+        return false;
+    }
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain.contains('.')
+                && ![".localhost", ".local", ".internal"]
+                    .iter()
+                    .any(|suffix| domain.ends_with(suffix))
+        }
+        Some(Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        None => false,
+    }
+}
+
+/// Returns `true` if the address is reachable on the Internet.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network" and the shared address space of the carrier-grade NATs.
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator_collects_all_errors() {
+        let mut validator = Validator::new(None);
+        validator
+            .check(true, "/name", "the name is required")
+            .check(false, "/email", "invalid email")
+            .check(false, "/consent", "consent is required");
+        match validator.finish() {
+            Err(ExecutionError::Validation(errors)) => assert_eq!(errors.errors.len(), 2),
+            _ => panic!("the validation should fail"),
+        }
+    }
+
+    #[test]
+    fn test_validator_reports_each_attribute_once() {
+        let mut validator = Validator::new(None);
+        validator
+            .check(false, "/fips_code", "the FIPS code must have 7 digits")
+            .check(false, "/fips_code", "the FIPS code is not a place of Texas");
+        match validator.finish() {
+            Err(ExecutionError::Validation(errors)) => {
+                assert_eq!(errors.errors.len(), 1);
+                assert_eq!(
+                    errors.errors[0].details(),
+                    "the FIPS code must have 7 digits"
+                );
+            }
+            _ => panic!("the validation should fail"),
+        }
+    }
+
+    #[test]
+    fn test_is_email() {
+        assert!(is_email("jane.doe@orgllc.com"));
+        assert!(!is_email("jane.doe"));
+        assert!(!is_email("@orgllc.com"));
+        assert!(!is_email("jane doe@orgllc.com"));
+        assert!(!is_email("jane@orgllc"));
+        assert!(!is_email("jane@orgllc."));
+        assert!(!is_email("jane@doe@orgllc.com"));
+    }
+
+    #[test]
+    fn test_is_fips_place_code() {
+        assert!(is_fips_place_code("3570670"));
+        assert!(!is_fips_place_code("0"));
+        assert!(!is_fips_place_code("357067a"));
+        assert!(!is_fips_place_code("35706700"));
+    }
+
+    #[test]
+    fn test_coordinates() {
+        assert!(is_latitude(35.04));
+        assert!(!is_latitude(90.1));
+        assert!(is_longitude(-85.3));
+        assert!(!is_longitude(-180.5));
+        assert!(!is_longitude(f64::NAN));
+    }
+
+    #[test]
+    fn test_is_public_https_url() {
+        assert!(is_public_https_url("https://example.com/hooks/bna"));
+        assert!(is_public_https_url("https://93.184.215.14/hooks"));
+        assert!(!is_public_https_url("http://example.com/hooks/bna"));
+        assert!(!is_public_https_url("example.com/hooks/bna"));
+        assert!(!is_public_https_url("https://localhost/hooks"));
+        assert!(!is_public_https_url("https://api.localhost/hooks"));
+        assert!(!is_public_https_url("https://metadata.google.internal/"));
+        assert!(!is_public_https_url("https://intranet/hooks"));
+        assert!(!is_public_https_url("https://127.0.0.1/hooks"));
+        assert!(!is_public_https_url(
+            "https://169.254.169.254/latest/meta-data"
+        ));
+        assert!(!is_public_https_url("https://10.0.0.1/hooks"));
+        assert!(!is_public_https_url("https://192.168.1.1/hooks"));
+        assert!(!is_public_https_url("https://[::1]/hooks"));
+        assert!(!is_public_https_url("https://[fd00::1]/hooks"));
+        assert!(!is_public_https_url("https://[fe80::1]/hooks"));
+        assert!(!is_public_https_url("https://[::ffff:127.0.0.1]/hooks"));
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        assert!(is_public_ip("8.8.8.8".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+        assert!(!is_public_ip("100.64.0.1".parse().unwrap()));
+        assert!(!is_public_ip("0.0.0.0".parse().unwrap()));
+        assert!(!is_public_ip("172.16.0.1".parse().unwrap()));
+    }
+}
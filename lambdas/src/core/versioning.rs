@@ -0,0 +1,204 @@
+//! Versioning of the API.
+//!
+//! The routes are mounted under a version prefix, `/v1` or `/v2`, each version being
+//! described by its own OpenAPI document. `/v2` introduces the breaking changes:
+//! - the pipeline steps are the ones actually recorded by the pipelines, i.e. `Setup`,
+//!   `Analysis`, `Save` and `Cleanup`,
+//! - the category scores of a rating are nested under `categories`, each category
+//!   exposing its `score`, and the bike infrastructure measures are nested under
+//!   `infrastructure`.
+//!
+//! The `/v1` routes are still served without prefix, for the clients written before
+//! the versioning. Once their deprecation date is set, these responses carry a
+//! `Deprecation` header, as defined by RFC 9745, and a link to their successor. The deprecation and removal dates of the
+//! unprefixed routes and of `/v1` are set in the `versions` configuration, the removal
+//! date being announced with the `Sunset` header defined by RFC 8594.
+use crate::core::{
+    config::config,
+    http_cache::http_date,
+    resource::{cities, pipelines, price, ratings, reports, usstates, webhooks},
+};
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use chrono::DateTime;
+use utoipa_axum::router::OpenApiRouter;
+
+/// Header announcing the deprecation of a route.
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Header announcing the removal date of a route.
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// Version of the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// Prefix of the routes of the version.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/v1",
+            ApiVersion::V2 => "/v2",
+        }
+    }
+
+    /// Name of the version, i.e. `v1`.
+    pub fn name(&self) -> &'static str {
+        self.prefix().trim_start_matches('/')
+    }
+
+    /// URL of the OpenAPI document describing the version.
+    pub fn specification_url(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/apidoc/v1/openapi.json",
+            ApiVersion::V2 => "/apidoc/v2/openapi.json",
+        }
+    }
+
+    /// Version of the OpenAPI document describing the version.
+    pub fn document_version(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "1.5.0",
+            ApiVersion::V2 => "2.0.0",
+        }
+    }
+
+    /// Returns the routes of the version, without their prefix.
+    ///
+    /// The system routes, i.e. the health checks, are not versioned.
+    pub fn routes(&self) -> OpenApiRouter {
+        let routes = OpenApiRouter::new()
+            .merge(price::endpoint::routes())
+            .merge(reports::endpoint::routes())
+            .merge(usstates::endpoint::routes())
+            .merge(webhooks::endpoint::routes());
+        match self {
+            ApiVersion::V1 => routes
+                .merge(cities::endpoint::routes())
+                .merge(pipelines::endpoint::routes())
+                .merge(ratings::endpoint::routes()),
+            ApiVersion::V2 => routes
+                .merge(cities::endpoint::routes_v2())
+                .merge(pipelines::endpoint::routes_v2())
+                .merge(ratings::endpoint::routes_v2()),
+        }
+    }
+}
+
+/// Returns a route without its version prefix, i.e. to look up its settings.
+pub fn unversioned_route(route: &str) -> &str {
+    [ApiVersion::V1, ApiVersion::V2]
+        .iter()
+        .find_map(|version| {
+            route
+                .strip_prefix(version.prefix())
+                .filter(|rest| rest.starts_with('/'))
+        })
+        .unwrap_or(route)
+}
+
+/// Middleware flagging the unprefixed routes as deprecated in favor of `/v1`.
+pub async fn deprecate_unversioned(request: Request, next: Next) -> Response {
+    let successor = format!("{}{}", ApiVersion::V1.prefix(), request.uri().path());
+    let mut response = next.run(request).await;
+    let versions = &config().versions;
+    announce_lifecycle(
+        response.headers_mut(),
+        versions.unversioned_deprecation.as_deref(),
+        versions.unversioned_sunset.as_deref(),
+        &successor,
+    );
+    response
+}
+
+/// Middleware announcing the deprecation and the removal of `/v1`, once planned.
+pub async fn track_v1_lifecycle(request: Request, next: Next) -> Response {
+    // The prefix was already stripped by the router.
+    let successor = format!("{}{}", ApiVersion::V2.prefix(), request.uri().path());
+    let mut response = next.run(request).await;
+    let versions = &config().versions;
+    announce_lifecycle(
+        response.headers_mut(),
+        versions.v1_deprecation.as_deref(),
+        versions.v1_sunset.as_deref(),
+        &successor,
+    );
+    response
+}
+
+/// Sets the `Deprecation` and `Sunset` headers of a response.
+///
+/// The successor is only linked if the route is deprecated. The dates are expected
+/// to be validated already, the invalid ones are ignored.
+fn announce_lifecycle(
+    headers: &mut HeaderMap,
+    deprecation: Option<&str>,
+    sunset: Option<&str>,
+    successor: &str,
+) {
+    if let Some(date) = deprecation.and_then(|d| DateTime::parse_from_rfc3339(d).ok()) {
+        headers.insert(
+            DEPRECATION_HEADER,
+            HeaderValue::from_str(&format!("@{}", date.timestamp()))
+                .expect("a timestamp is a valid header value"),
+        );
+        if let Ok(link) =
+            HeaderValue::from_str(&format!("<{successor}>; rel=\"successor-version\""))
+        {
+            headers.append(header::LINK, link);
+        }
+    }
+    if let Some(date) = sunset.and_then(|d| DateTime::parse_from_rfc3339(d).ok()) {
+        headers.insert(
+            SUNSET_HEADER,
+            HeaderValue::from_str(&http_date(&date)).expect("a date is a valid header value"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unversioned_route() {
+        assert_eq!(
+            unversioned_route("/v1/ratings/{rating_id}"),
+            "/ratings/{rating_id}"
+        );
+        assert_eq!(unversioned_route("/v2/reports"), "/reports");
+        assert_eq!(unversioned_route("/v10/reports"), "/v10/reports");
+        assert_eq!(unversioned_route("/health"), "/health");
+    }
+
+    #[test]
+    fn test_announce_lifecycle() {
+        let mut headers = HeaderMap::new();
+        announce_lifecycle(
+            &mut headers,
+            Some("2026-10-19T00:00:00Z"),
+            Some("2027-06-30T00:00:00Z"),
+            "/v1/ratings",
+        );
+        assert_eq!(headers[DEPRECATION_HEADER], "@1792368000");
+        assert_eq!(headers[SUNSET_HEADER], "Wed, 30 Jun 2027 00:00:00 GMT");
+        assert_eq!(
+            headers[header::LINK],
+            "</v1/ratings>; rel=\"successor-version\""
+        );
+    }
+
+    #[test]
+    fn test_no_lifecycle() {
+        let mut headers = HeaderMap::new();
+        announce_lifecycle(&mut headers, None, None, "/v2/ratings");
+        assert!(headers.is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+//! Webhook notifications.
+//!
+//! Events are written to the `webhook_outbox` table, within the same transaction as
+//! the change triggering them, one entry per interested subscription. A dispatcher
+//! then delivers them, signing each payload with HMAC-SHA256, and retries the failed
+//! deliveries with an exponential backoff.
+//!
+//! Several dispatchers may run concurrently: each of them claims a batch of deliveries
+//! for a limited amount of time, during which the other ones skip it.
+use super::{telemetry, validation::is_public_ip};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use entity::{
+    webhook_outbox, webhook_subscription,
+    wrappers::{WebhookDeliveryStatus, WebhookEvent},
+};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sea_orm::{
+    sea_query::{Expr, LockBehavior, LockType},
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{collections::HashMap, sync::Arc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Header containing the event type.
+pub const EVENT_HEADER: &str = "X-BNA-Event";
+/// Header containing the delivery identifier.
+pub const DELIVERY_HEADER: &str = "X-BNA-Delivery";
+/// Header containing the signature of the payload, formatted as `sha256=<hex digest>`.
+pub const SIGNATURE_HEADER: &str = "X-BNA-Signature";
+/// Maximum number of attempts before giving up on a delivery.
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Delay before the first retry.
+const BACKOFF_BASE_SECONDS: i64 = 30;
+/// Maximum delay between two attempts.
+const BACKOFF_MAX_SECONDS: i64 = 6 * 60 * 60;
+/// Maximum amount of time to wait for a subscriber to respond.
+const DELIVERY_TIMEOUT_SECONDS: u64 = 10;
+/// Extra time given to a dispatcher to record the outcome of its claimed deliveries.
+const CLAIM_MARGIN_SECONDS: i64 = 60;
+
+/// Records an event for all the active subscriptions interested in it.
+///
+/// `db` is expected to be the transaction performing the change which triggered the
+/// event. Returns the number of deliveries which were scheduled.
+pub async fn record_event<C>(db: &C, event: WebhookEvent, data: Value) -> Result<usize, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let event_type = event.to_string();
+    let subscriptions = webhook_subscription::Entity::find()
+        .filter(webhook_subscription::Column::Active.eq(true))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|s| subscribes_to(s, &event_type))
+        .collect::<Vec<_>>();
+    if subscriptions.is_empty() {
+        return Ok(0);
+    }
+
+    let now = now();
+    let payload = json!({
+        "id": Uuid::new_v4(),
+        "type": event_type,
+        "created_at": now,
+        "data": data,
+    });
+    let deliveries = subscriptions
+        .iter()
+        .map(|s| webhook_outbox::ActiveModel {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            subscription_id: ActiveValue::Set(s.id),
+            event_type: ActiveValue::Set(event_type.clone()),
+            payload: ActiveValue::Set(payload.clone()),
+            status: ActiveValue::Set(WebhookDeliveryStatus::Pending.to_string()),
+            attempts: ActiveValue::Set(0),
+            next_attempt_at: ActiveValue::Set(now),
+            last_error: ActiveValue::NotSet,
+            created_at: ActiveValue::Set(now),
+            delivered_at: ActiveValue::NotSet,
+        })
+        .collect::<Vec<_>>();
+    let count = deliveries.len();
+    webhook_outbox::Entity::insert_many(deliveries)
+        .exec(db)
+        .await?;
+    info!("{count} delivery(ies) scheduled for event {event_type}");
+
+    Ok(count)
+}
+
+/// Returns `true` if the subscription is interested in the event type.
+fn subscribes_to(subscription: &webhook_subscription::Model, event_type: &str) -> bool {
+    subscription
+        .events
+        .as_array()
+        .is_some_and(|events| events.iter().any(|e| e.as_str() == Some(event_type)))
+}
+
+/// Signs a payload with HMAC-SHA256 and returns the hex encoded digest.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Returns the delay to wait before the next attempt.
+///
+/// The delay doubles after each attempt, and is capped to a few hours.
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 30) as u32;
+    let seconds = BACKOFF_BASE_SECONDS
+        .saturating_mul(2_i64.saturating_pow(exponent))
+        .min(BACKOFF_MAX_SECONDS);
+    Duration::seconds(seconds)
+}
+
+/// Summary of a dispatch run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchReport {
+    /// Number of successful deliveries.
+    pub delivered: usize,
+    /// Number of failed deliveries which will be retried.
+    pub retried: usize,
+    /// Number of deliveries which were abandoned.
+    pub failed: usize,
+}
+
+/// Delivers the pending events which are due.
+pub async fn dispatch_pending(
+    db: &DatabaseConnection,
+    client: &reqwest::Client,
+    batch_size: u64,
+) -> Result<DispatchReport, DbErr> {
+    let now = now();
+    let claimed_until = now + claim_duration(batch_size);
+    let deliveries = claim_pending(db, batch_size, now, claimed_until).await?;
+    let subscription_ids = deliveries
+        .iter()
+        .map(|d| d.subscription_id)
+        .collect::<Vec<_>>();
+    let subscriptions = webhook_subscription::Entity::find()
+        .filter(webhook_subscription::Column::Id.is_in(subscription_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s))
+        .collect::<HashMap<_, _>>();
+
+    let mut report = DispatchReport::default();
+    for delivery in deliveries {
+        let subscription = subscriptions
+            .get(&delivery.subscription_id)
+            .filter(|s| s.active);
+        let result = match subscription {
+            Some(subscription) => deliver(client, subscription, &delivery).await,
+            None => Err("the subscription is not active".to_string()),
+        };
+
+        let now = self::now();
+        let attempts = delivery.attempts + 1;
+        let id = delivery.id;
+        let mut active_model = delivery.into_active_model();
+        active_model.attempts = ActiveValue::Set(attempts);
+        match result {
+            Ok(()) => {
+                active_model.status =
+                    ActiveValue::Set(WebhookDeliveryStatus::Delivered.to_string());
+                active_model.delivered_at = ActiveValue::Set(Some(now));
+                active_model.last_error = ActiveValue::Set(None);
+                report.delivered += 1;
+            }
+            Err(e) => {
+                warn!("delivery {id} failed (attempt {attempts}): {e}");
+                if attempts >= MAX_ATTEMPTS || subscription.is_none() {
+                    active_model.status =
+                        ActiveValue::Set(WebhookDeliveryStatus::Failed.to_string());
+                    report.failed += 1;
+                } else {
+                    active_model.next_attempt_at = ActiveValue::Set(now + backoff(attempts));
+                    report.retried += 1;
+                }
+                active_model.last_error = ActiveValue::Set(Some(e));
+            }
+        }
+
+        // Only record the outcome if the claim did not expire in the meantime.
+        let result = webhook_outbox::Entity::update_many()
+            .set(active_model)
+            .filter(webhook_outbox::Column::Id.eq(id))
+            .filter(webhook_outbox::Column::NextAttemptAt.eq(claimed_until))
+            .exec(db)
+            .await?;
+        if result.rows_affected == 0 {
+            warn!("the claim of delivery {id} expired before its outcome was recorded");
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns how long a dispatcher may keep a batch of deliveries.
+///
+/// The deliveries are sent one after the other, therefore the whole batch may take up
+/// to its size times the delivery timeout.
+fn claim_duration(batch_size: u64) -> Duration {
+    let timeout =
+        i64::try_from(DELIVERY_TIMEOUT_SECONDS.saturating_mul(batch_size)).unwrap_or(i64::MAX / 2);
+    Duration::seconds(timeout.saturating_add(CLAIM_MARGIN_SECONDS))
+}
+
+/// Claims the pending deliveries which are due, until `claimed_until`.
+///
+/// The rows locked by another dispatcher are skipped, and the next attempt of the
+/// claimed ones is postponed, so that they are not claimed again before the claim
+/// expires, i.e. if the dispatcher crashes.
+async fn claim_pending(
+    db: &DatabaseConnection,
+    batch_size: u64,
+    now: DateTime<FixedOffset>,
+    claimed_until: DateTime<FixedOffset>,
+) -> Result<Vec<webhook_outbox::Model>, DbErr> {
+    let txn = db.begin().await?;
+    let mut deliveries = webhook_outbox::Entity::find()
+        .filter(webhook_outbox::Column::Status.eq(WebhookDeliveryStatus::Pending.to_string()))
+        .filter(webhook_outbox::Column::NextAttemptAt.lte(now))
+        .order_by_asc(webhook_outbox::Column::NextAttemptAt)
+        .limit(batch_size)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await?;
+    if !deliveries.is_empty() {
+        webhook_outbox::Entity::update_many()
+            .col_expr(
+                webhook_outbox::Column::NextAttemptAt,
+                Expr::value(claimed_until),
+            )
+            .filter(webhook_outbox::Column::Id.is_in(deliveries.iter().map(|d| d.id)))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    for delivery in &mut deliveries {
+        delivery.next_attempt_at = claimed_until;
+    }
+    Ok(deliveries)
+}
+
+/// Creates the HTTP client delivering the events.
+///
+/// The client does not follow the redirections and refuses to connect to the
+/// non-public addresses, so that the subscribers cannot reach the internal services.
+pub fn client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .timeout(std::time::Duration::from_secs(DELIVERY_TIMEOUT_SECONDS))
+        .build()
+}
+
+/// Resolves the host names to their public addresses only.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Sends an event to a subscriber.
+async fn deliver(
+    client: &reqwest::Client,
+    subscription: &webhook_subscription::Model,
+    delivery: &webhook_outbox::Model,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(&delivery.payload).map_err(|e| e.to_string())?;
+    let signature = sign(&subscription.secret, &body);
+    let mut headers = reqwest::header::HeaderMap::new();
+    telemetry::inject_trace_context(&mut headers);
+    client
+        .post(&subscription.url)
+        .headers(headers)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, format!("sha256={signature}"))
+        .body(body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&FixedOffset::east_opt(0).expect("a valid offset"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::seconds(30));
+        assert_eq!(backoff(2), Duration::seconds(60));
+        assert_eq!(backoff(3), Duration::seconds(120));
+        assert_eq!(backoff(30), Duration::seconds(BACKOFF_MAX_SECONDS));
+    }
+
+    #[test]
+    fn test_claim_duration() {
+        assert_eq!(claim_duration(1), Duration::seconds(70));
+        assert_eq!(claim_duration(100), Duration::seconds(1060));
+    }
+}
@@ -1,76 +1,186 @@
 pub mod core;
 
 use axum::{
-    extract::{FromRequest, OriginalUri},
+    extract::{FromRequestParts, OriginalUri},
+    http::request::Parts,
+    middleware::Next,
     response::IntoResponse,
 };
 use bnacore::aws::get_aws_secrets_value;
+use core::{
+    config::{config, SqlLogLevel},
+    queue::QueueError,
+};
 use effortless::{
-    error::{APIError, APIErrors},
+    error::{APIError, APIErrorSource, APIErrors, PROBLEM_JSON, PROBLEM_TYPE},
     fragment::BnaRequestExt,
 };
 use lambda_http::{
     http::{header, StatusCode},
     Body, Error, Request, Response,
 };
-use once_cell::sync::OnceCell;
-use sea_orm::{Database, DatabaseConnection, DbErr};
+use sea_orm::{sqlx, ConnectOptions, Database, DatabaseConnection, DbErr, RuntimeErr, SqlErr};
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::env;
-use tracing::{debug, error};
-
-/// Maximum number of items allowed to be returned by a query at once.
-pub const MAX_PAGE_SIZE: u64 = 100;
-/// Number of items to return per page if no argument was provided.
-pub const DEFAULT_PAGE_SIZE: u64 = 50;
-
-/// Global static database connection.
-pub static DB_CONN: OnceCell<DatabaseConnection> = OnceCell::new();
+use std::{
+    env,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, warn};
 
 /// The result type to return to the caller of the Lambda API handler.
 pub type APIResult<T> = std::result::Result<T, Response<Body>>;
 
-// /// Maximum number of items allowed to be returned by a query at once.
-// pub const MAX_PAGE_SIZE: u64 = 100;
-// /// Number of items to return per page if no argument was provided.
-// pub const DEFAULT_PAGE_SIZE: u64 = 50;
-
 /// Returns the database connection from a custom environment variable or secret.
 ///
 /// Look up for the connection string:
 ///   - first inside the value of the `name` environment variable,
 ///   - then in the AWS Secrets Manager with the `name`  and  `secret_key` provided.
+///
+/// The connection pool is sized according to the `database` configuration.
 pub async fn database_connect_custom(
     name: &str,
     secret_key: &str,
 ) -> Result<DatabaseConnection, DbErr> {
-    let database_url: String = match env::var(name) {
-        Ok(value) => value,
-        Err(_) => get_aws_secrets_value(name, secret_key).await.map_err(|e| DbErr::Custom(format!("Cannot find the connection string within the secret {name}. Ensure `{secret_key}` is correctly set: {e}")))?,
-    };
-    Database::connect(database_url).await
+    database_connect_url(lookup_database_url(name, secret_key).await?).await
+}
+
+/// Looks up a connection string in the `name` environment variable, then in the AWS
+/// Secrets Manager.
+async fn lookup_database_url(name: &str, secret_key: &str) -> Result<String, DbErr> {
+    match env::var(name) {
+        Ok(value) => Ok(value),
+        Err(_) => get_aws_secrets_value(name, secret_key).await.map_err(|e| DbErr::Custom(format!("Cannot find the connection string within the secret {name}. Ensure `{secret_key}` is correctly set: {e}"))),
+    }
 }
 
 /// Returns the database connection.
 ///
-/// Look up for the database connection string a standard `DATABASE_URL` environment
-/// variable or secret.
+/// Look up for the database connection string in the configuration, then in a
+/// standard `DATABASE_URL` environment variable or secret.
 pub async fn database_connect() -> Result<DatabaseConnection, DbErr> {
+    database_connect_url(database_url().await?).await
+}
+
+/// Returns the connection string of the database.
+///
+/// Look up for it in the configuration, then in a standard `DATABASE_URL`
+/// environment variable or secret.
+pub async fn database_url() -> Result<String, DbErr> {
     const DATABASE_URL_KEY: &str = "DATABASE_URL";
-    database_connect_custom(DATABASE_URL_KEY, DATABASE_URL_KEY).await
+    match &config().database.url {
+        Some(url) => Ok(url.clone()),
+        None => lookup_database_url(DATABASE_URL_KEY, DATABASE_URL_KEY).await,
+    }
 }
 
-pub(crate) async fn database_connect_or_init() -> Result<&'static DatabaseConnection, DbErr> {
-    if let Some(db) = DB_CONN.get() {
-        debug!("Database connection already set in cache.");
-        Ok(db)
+/// Connects to the database with the pool settings of the configuration.
+async fn database_connect_url(database_url: String) -> Result<DatabaseConnection, DbErr> {
+    let settings = &config().database;
+    let mut options = ConnectOptions::new(database_url);
+    options
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections)
+        .acquire_timeout(Duration::from_secs(settings.acquire_timeout))
+        .idle_timeout(Duration::from_secs(settings.idle_timeout))
+        // The connections may be broken after a Lambda freeze, therefore they are
+        // checked before being used.
+        .test_before_acquire(true)
+        .sqlx_logging(settings.sql_log_level != SqlLogLevel::Off)
+        .sqlx_logging_level(settings.sql_log_level.into());
+    if settings.statement_timeout > 0 {
+        let statement_timeout = (settings.statement_timeout * 1000).to_string();
+        options.map_sqlx_postgres_opts(move |opts| {
+            opts.options([("statement_timeout", statement_timeout.as_str())])
+        });
+    }
+    Database::connect(options).await
+}
+
+/// Returns the database connection to the read replica.
+///
+/// Look up for the connection string of the replica in the configuration. The primary
+/// database connection is returned if no replica is configured.
+pub async fn database_read_connect() -> Result<DatabaseConnection, DbErr> {
+    match &config().database.read_url {
+        Some(url) => database_connect_url(url.clone()).await,
+        None => database_connect().await,
+    }
+}
+
+/// Database connection cached across the invocations of a warm Lambda.
+struct CachedConnection {
+    db: DatabaseConnection,
+    last_used: Instant,
+}
+
+/// Global static connection to the primary database.
+static DB_CONN: Mutex<Option<CachedConnection>> = Mutex::new(None);
+/// Global static connection to the read replica.
+static DB_READ_CONN: Mutex<Option<CachedConnection>> = Mutex::new(None);
+
+/// Idle time after which the cached connection is checked before being reused.
+///
+/// A Lambda may be frozen for a long time between two invocations, leaving the
+/// connections to the database broken.
+const CONNECTION_CHECK_AFTER: Duration = Duration::from_secs(60);
+
+/// Returns the cached connection to the primary database, connecting first if needed.
+pub(crate) async fn database_connect_or_init() -> Result<DatabaseConnection, DbErr> {
+    cached_connection(&DB_CONN, database_connect).await
+}
+
+/// Returns the cached connection to the read replica, connecting first if needed.
+///
+/// The primary database is used if no replica is configured.
+pub(crate) async fn database_read_connect_or_init() -> Result<DatabaseConnection, DbErr> {
+    if config().database.read_url.is_none() {
+        return database_connect_or_init().await;
+    }
+    cached_connection(&DB_READ_CONN, database_read_connect).await
+}
+
+/// Returns the connection of the cache, reconnecting if it went bad.
+async fn cached_connection<F, Fut>(
+    cache: &Mutex<Option<CachedConnection>>,
+    connect: F,
+) -> Result<DatabaseConnection, DbErr>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<DatabaseConnection, DbErr>>,
+{
+    let cached = cache
+        .lock()
+        .expect("a healthy lock")
+        .as_ref()
+        .map(|c| (c.db.clone(), c.last_used.elapsed()));
+    if let Some((db, idle)) = cached {
+        if idle < CONNECTION_CHECK_AFTER || db.ping().await.is_ok() {
+            debug!("Database connection already set in cache.");
+            touch(cache, &db);
+            return Ok(db);
+        }
+        warn!("The cached database connection is broken. Reconnecting...");
     } else {
         debug!("Database connection not found in cache. Connecting...");
-        let db = database_connect().await?;
-        DB_CONN.set(db.clone()).ok();
-        Ok(DB_CONN.get().unwrap())
     }
+    let mut db = connect().await?;
+    db.set_metric_callback(|info| {
+        core::metrics::observe_db_query(info);
+        core::telemetry::record_db_query(info);
+    });
+    touch(cache, &db);
+    Ok(db)
+}
+
+/// Stores the connection in the cache, marking it as just used.
+fn touch(cache: &Mutex<Option<CachedConnection>>, db: &DatabaseConnection) {
+    *cache.lock().expect("a healthy lock") = Some(CachedConnection {
+        db: db.clone(),
+        last_used: Instant::now(),
+    });
 }
 
 /// Builds a paginated Response.
@@ -105,7 +215,10 @@ pub fn build_paginated_response(
 
     // Build the response.
     Ok(Response::builder()
-        .header("link", paginatron.link_header(Some(DEFAULT_PAGE_SIZE)))
+        .header(
+            "link",
+            paginatron.link_header(Some(config().pagination.default_page_size)),
+        )
         .header("x-next-page", nav.next())
         .header("x-page", page)
         .header("x-per-page", page_size)
@@ -261,6 +374,24 @@ where
     pub fn payload(&self) -> &T {
         &self.payload
     }
+
+    /// Converts the payload, keeping the pagination.
+    pub fn map<U, F>(self, f: F) -> PageFlow<U>
+    where
+        U: Serialize,
+        F: FnOnce(T) -> U,
+    {
+        PageFlow::new(self.paginatron, f(self.payload))
+    }
+
+    /// Converts the payload with a fallible conversion, keeping the pagination.
+    pub fn try_map<U, E, F>(self, f: F) -> Result<PageFlow<U>, E>
+    where
+        U: Serialize,
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        Ok(PageFlow::new(self.paginatron, f(self.payload)?))
+    }
 }
 
 impl<T> From<PageFlow<T>> for Response<Body>
@@ -272,7 +403,9 @@ where
         Response::builder()
             .header(
                 "link",
-                value.paginatron.link_header(Some(DEFAULT_PAGE_SIZE)),
+                value
+                    .paginatron
+                    .link_header(Some(config().pagination.default_page_size)),
             )
             .header("x-next-page", nav.next())
             .header("x-page", value.paginatron.page)
@@ -295,7 +428,11 @@ where
     fn into_response(self) -> axum::response::Response {
         let nav = self.paginatron.navigation();
         let r: Response<axum::body::Body> = axum::response::Response::builder()
-            .header("link", self.paginatron.link_header(Some(DEFAULT_PAGE_SIZE)))
+            .header(
+                "link",
+                self.paginatron
+                    .link_header(Some(config().pagination.default_page_size)),
+            )
             .header("x-next-page", nav.next())
             .header("x-page", self.paginatron.page)
             .header("x-per-page", self.paginatron.page_size)
@@ -352,106 +489,413 @@ pub async fn api_database_connect(event: &Request) -> APIResult<DatabaseConnecti
     }
 }
 
+/// Error raised while processing a request.
+///
+/// Every variant carries the ID of the request and the source of the error, so that
+/// the error can be reported without the context of the request being processed.
 #[derive(Debug, thiserror::Error)]
 pub enum ExecutionError {
     ///  An error from unsuccessful database operations.
-    #[error("database error: {0:?}")]
-    DatabaseError(#[from] sea_orm::DbErr),
+    #[error("database error: {2:?}")]
+    DatabaseError(Option<String>, String, sea_orm::DbErr),
 
-    /// Unexpected error.
-    #[error("unexpected error: {0} {1}")]
-    Unexpected(String, String),
+    /// Unexpected error, with its details only being logged.
+    #[error("unexpected error: {2} {3}")]
+    Unexpected(Option<String>, String, String, String),
 
     /// Entry not found.
     #[error("entry not found at {1}: {2}")]
     NotFound(Option<String>, String, String),
 
     /// Uncovered country.
-    #[error("the country is not covered by the analyzer: {0}")]
-    UncoveredCountry(String),
+    #[error("the country is not covered by the analyzer: {2}")]
+    UncoveredCountry(Option<String>, String, String),
 
     /// Invalid US State.
-    #[error("the state is not a valid US state: {0}")]
-    InvalidUSState(String),
+    #[error("the state is not a valid US state: {2}")]
+    InvalidUSState(Option<String>, String, String),
+
+    /// An error from the analysis queue.
+    #[error("queue error: {2}")]
+    QueueError(Option<String>, String, core::queue::QueueError),
+
+    /// Invalid attribute in the request body.
+    #[error("invalid attribute {1}: {2}")]
+    InvalidAttribute(Option<String>, String, String),
+
+    /// The caller is not authenticated.
+    #[error("unauthorized access to {1}: {2}")]
+    Unauthorized(Option<String>, String, String),
+
+    /// The caller is not allowed to perform the request.
+    #[error("forbidden access to {1}: {2}")]
+    Forbidden(Option<String>, String, String),
+
+    /// The request conflicts with the current state of the resource.
+    #[error("conflict at {1}: {2}")]
+    Conflict(Option<String>, String, String),
+
+    /// The request payload is invalid.
+    #[error("invalid payload: {} error(s)", .0.errors.len())]
+    Validation(APIErrors),
 }
 
-impl From<ExecutionError> for APIError {
-    fn from(value: ExecutionError) -> Self {
-        match value {
-            ExecutionError::DatabaseError(_) => APIError::db_error(None, "Unknown source", ""),
-            ExecutionError::NotFound(id, source, message) => {
-                APIError::not_found(id, &source, &message)
+/// Returns the ID and the source of the request being processed, if any.
+///
+/// Only used to describe the errors converted with `?`, when they are raised.
+fn current_request() -> (Option<String>, String) {
+    Context::current()
+        .map(|ctx| (ctx.request_id(), ctx.source()))
+        .unwrap_or((None, String::from("Unknown source")))
+}
+
+impl From<DbErr> for ExecutionError {
+    fn from(value: DbErr) -> Self {
+        let (request_id, source) = current_request();
+        ExecutionError::DatabaseError(request_id, source, value)
+    }
+}
+
+impl From<QueueError> for ExecutionError {
+    fn from(value: QueueError) -> Self {
+        let (request_id, source) = current_request();
+        ExecutionError::QueueError(request_id, source, value)
+    }
+}
+
+/// Number of seconds a client should wait before retrying when a dependency of the
+/// API is unavailable.
+const RETRY_AFTER_SECONDS: u64 = 5;
+
+/// Maps an error to the API error best describing it.
+///
+/// The validation errors are reported field by field by the conversion to
+/// [`APIErrors`] instead.
+fn api_error(value: ExecutionError) -> APIError {
+    match value {
+        ExecutionError::DatabaseError(request_id, source, e) => {
+            database_error(&e, request_id, source)
+        }
+        ExecutionError::Unexpected(request_id, source, message, details) => {
+            error!("unexpected error: {message} {details}");
+            APIError::internal_error(request_id, "Internal Error", &message, &source)
+        }
+        ExecutionError::NotFound(id, source, message) => APIError::not_found(id, &source, &message),
+        ExecutionError::UncoveredCountry(request_id, _, country) => APIError::new(
+            request_id,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Uncovered Country",
+            &format!("the country is not covered by the analyzer: {country}"),
+            Some(APIErrorSource::Pointer(String::from("/country"))),
+        ),
+        ExecutionError::InvalidUSState(request_id, _, state) => APIError::new(
+            request_id,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Invalid US State",
+            &format!("the state is not a valid US state: {state}"),
+            Some(APIErrorSource::Pointer(String::from("/state"))),
+        ),
+        ExecutionError::QueueError(request_id, source, e) => match e {
+            QueueError::Send(_) => {
+                error!("{e}");
+                APIError::new(
+                    request_id,
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Service Unavailable",
+                    "the analysis queue is unavailable",
+                    Some(APIErrorSource::Pointer(source)),
+                )
+            }
+            QueueError::Serialization(_) => {
+                error!("{e}");
+                APIError::internal_error(
+                    request_id,
+                    "Internal Error",
+                    "cannot prepare the analysis message",
+                    &source,
+                )
             }
-            _ => APIError::internal_error(
-                None,
-                "Internal Error",
-                "Unknown details",
-                "Unknown source",
-            ),
+        },
+        ExecutionError::InvalidAttribute(id, pointer, message) => {
+            APIError::with_pointer(id, &pointer, &message)
+        }
+        ExecutionError::Unauthorized(id, source, message) => APIError::new(
+            id,
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized",
+            &message,
+            Some(APIErrorSource::Pointer(source)),
+        ),
+        ExecutionError::Forbidden(id, source, message) => APIError::new(
+            id,
+            StatusCode::FORBIDDEN,
+            "Forbidden",
+            &message,
+            Some(APIErrorSource::Pointer(source)),
+        ),
+        ExecutionError::Conflict(id, source, message) => APIError::new(
+            id,
+            StatusCode::CONFLICT,
+            "Conflict",
+            &message,
+            Some(APIErrorSource::Pointer(source)),
+        ),
+        // The request ID is added when the error is rendered.
+        ExecutionError::Validation(_) => APIError::new(
+            None,
+            StatusCode::BAD_REQUEST,
+            "Bad Request",
+            "the request payload is invalid",
+            None,
+        ),
+    }
+}
+
+/// Maps a database error to the API error best describing it.
+fn database_error(e: &DbErr, request_id: Option<String>, source: String) -> APIError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            return APIError::new(
+                request_id,
+                StatusCode::CONFLICT,
+                "Conflict",
+                "the entry already exists",
+                Some(APIErrorSource::Pointer(source)),
+            )
+        }
+        Some(SqlErr::ForeignKeyConstraintViolation(_)) => {
+            return APIError::new(
+                request_id,
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Unprocessable Entity",
+                "the entry references a resource which does not exist",
+                Some(APIErrorSource::Pointer(source)),
+            )
+        }
+        _ => {}
+    }
+    match e {
+        DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => {
+            error!("{e}");
+            APIError::new(
+                request_id,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service Unavailable",
+                "the database is unavailable",
+                Some(APIErrorSource::Pointer(source)),
+            )
+        }
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx_error))
+        | DbErr::Query(RuntimeErr::SqlxError(sqlx_error))
+            if matches!(
+                sqlx_error,
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+            ) =>
+        {
+            error!("{e}");
+            APIError::new(
+                request_id,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service Unavailable",
+                "the database is unavailable",
+                Some(APIErrorSource::Pointer(source)),
+            )
+        }
+        DbErr::RecordNotFound(message) => APIError::not_found(request_id, &source, message),
+        DbErr::RecordNotUpdated => {
+            APIError::not_found(request_id, &source, "the entry to update was not found")
+        }
+        _ => {
+            error!("{e}");
+            APIError::db_error(request_id, &source, "the database operation failed")
         }
     }
 }
 
 impl From<ExecutionError> for APIErrors {
     fn from(value: ExecutionError) -> Self {
-        APIError::from(value).into()
+        match value {
+            // Each invalid attribute is reported in its own error object.
+            ExecutionError::Validation(errors) if !errors.is_empty() => errors,
+            _ => api_error(value).into(),
+        }
     }
 }
 
 impl IntoResponse for ExecutionError {
     fn into_response(self) -> axum::response::Response {
-        let api_error = APIError::from(self);
-        let api_errors = APIErrors::from(api_error);
-        let status = if api_errors.errors.len() == 1 {
-            let first = api_errors.errors.first().unwrap();
-            first.status().parse::<u16>().expect("invalid status code")
-        } else {
-            StatusCode::BAD_REQUEST.as_u16()
-        };
-        // let status = status;
-        Response::builder()
-            .status(status)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(json!(api_errors).to_string().into())
-            .unwrap()
+        error_response(APIErrors::from(self))
+    }
+}
+
+/// Renders the errors in the format negotiated by the request being processed.
+///
+/// The errors are rendered as JSON:API error objects by default, or as RFC 9457
+/// problem details if the client prefers `application/problem+json`.
+pub fn error_response(errors: APIErrors) -> axum::response::Response {
+    let ctx = Context::current();
+    // Every error reports the request it occurred on.
+    let errors = APIErrors {
+        errors: errors
+            .errors
+            .into_iter()
+            .map(|e| e.with_default_id(ctx.as_ref().and_then(Context::request_id)))
+            .collect(),
+    };
+    let status = errors.status_code();
+    let (content_type, body) = match ctx {
+        Some(ref ctx) if ctx.problem_details => {
+            let mut problem = errors.to_problem_details(Some(&ctx.source));
+            problem.problem_type = problem_type(status);
+            (PROBLEM_JSON, json!(problem))
+        }
+        _ => ("application/json", json!(errors)),
+    };
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::VARY, header::ACCEPT.as_str());
+    if status == StatusCode::SERVICE_UNAVAILABLE {
+        response = response.header(header::RETRY_AFTER, RETRY_AFTER_SECONDS);
     }
+    response.body(body.to_string().into()).unwrap()
 }
 
+/// Base of the URIs identifying the types of the problems reported by the API.
+const PROBLEM_TYPE_BASE: &str = "https://api.bna.peopleforbikes.org/problems";
+
+/// Returns the URI identifying the type of the problems reported with a status code.
+///
+/// Every kind of error is reported with its own status code, i.e. the invalid requests
+/// with `400` or the conflicts with `409`. The other problems, like the internal
+/// errors, have no semantics beyond their status code and keep the `about:blank` type.
+fn problem_type(status: StatusCode) -> String {
+    let kind = match status {
+        StatusCode::BAD_REQUEST => "validation",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not-found",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        _ => return String::from(PROBLEM_TYPE),
+    };
+    format!("{PROBLEM_TYPE_BASE}/{kind}")
+}
+
+tokio::task_local! {
+    /// Context of the request being processed.
+    static REQUEST_CONTEXT: Context;
+}
+
+/// Middleware making the context of the request available while it is processed.
+///
+/// It allows the errors raised without context, i.e. from the database, to report
+/// the request they originate from.
+pub async fn with_request_context(
+    ctx: Context,
+    request: axum::extract::Request,
+    next: Next,
+) -> axum::response::Response {
+    REQUEST_CONTEXT.scope(ctx, next.run(request)).await
+}
+
+#[derive(Clone)]
 pub struct Context {
     // request_id: APIGatewayV2RequestID,
     request_id: Option<String>,
     source: String,
+    principal: Option<String>,
+    groups: Vec<String>,
+    problem_details: bool,
 }
 
-impl<S> FromRequest<S> for Context
+impl<S> FromRequestParts<S> for Context
 where
     S: Send + Sync,
 {
     type Rejection = (StatusCode, &'static str);
 
-    async fn from_request(req: axum::extract::Request, _: &S) -> Result<Self, Self::Rejection> {
-        let request_context = req
-            .extensions()
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let request_context = parts
+            .extensions
             .get::<lambda_http::request::RequestContext>();
-        let request_id = match request_context {
+        let (request_id, principal, groups) = match request_context {
             Some(lambda_http::request::RequestContext::ApiGatewayV2(ref ctx)) => {
-                ctx.request_id.clone()
+                // Identify the caller from the claims validated by the JWT authorizer.
+                let claims = ctx
+                    .authorizer
+                    .as_ref()
+                    .and_then(|a| a.jwt.as_ref())
+                    .map(|jwt| &jwt.claims);
+                let principal = claims
+                    .and_then(|c| c.get("email").or(c.get("sub")))
+                    .cloned();
+                let groups = claims
+                    .and_then(|c| c.get(GROUPS_CLAIM))
+                    .map(|g| parse_groups(g))
+                    .unwrap_or_default();
+                (ctx.request_id.clone(), principal, groups)
+            }
+            // Without API Gateway, the standalone server may trust the identity
+            // headers set by the developers and the tests.
+            _ if config().features.trust_identity_headers => {
+                let header = |name| parts.headers.get(name).and_then(|v| v.to_str().ok());
+                let principal = header(PRINCIPAL_HEADER)
+                    .filter(|p| !p.trim().is_empty())
+                    .map(String::from);
+                let groups = header(GROUPS_HEADER).map(parse_groups).unwrap_or_default();
+                (None, principal, groups)
             }
-            _ => None,
+            _ => (None, None, Vec::new()),
         };
-        let uri = req.extensions().get::<OriginalUri>();
+        // The API Gateway request id, which appears in its access logs, prevails over
+        // the request id header, set for every other request and echoed in the response.
+        let request_id = request_id.or_else(|| {
+            parts
+                .headers
+                .get(core::telemetry::REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        });
+        let problem_details = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(prefers_problem_details);
+        let uri = parts.extensions.get::<OriginalUri>();
         let source = match uri {
             Some(path) => path.0.path().to_owned(),
-            None => req.uri().path().to_owned(),
+            None => parts.uri.path().to_owned(),
         };
 
-        Ok(Self { request_id, source })
+        Ok(Self {
+            request_id,
+            source,
+            principal,
+            groups,
+            problem_details,
+        })
     }
 }
 
 impl Context {
+    /// Returns the context of the request being processed, if any.
+    pub fn current() -> Option<Self> {
+        REQUEST_CONTEXT.try_with(Clone::clone).ok()
+    }
+
     pub fn new(request_id: Option<String>, source: String) -> Self {
-        Self { request_id, source }
+        Self {
+            request_id,
+            source,
+            principal: None,
+            groups: Vec::new(),
+            problem_details: false,
+        }
     }
 
     pub fn request_id(&self) -> Option<String> {
@@ -461,6 +905,60 @@ impl Context {
     pub fn source(&self) -> String {
         self.source.to_owned()
     }
+
+    /// Returns the identity of the authenticated caller, if any.
+    pub fn principal(&self) -> Option<String> {
+        self.principal.clone()
+    }
+
+    /// Returns `true` if the authenticated caller belongs to the administrators.
+    pub fn is_admin(&self) -> bool {
+        self.groups.iter().any(|g| g == ADMIN_GROUP)
+    }
+}
+
+/// Returns `true` if the `Accept` header prefers problem details over JSON.
+///
+/// The JSON:API format remains the default, therefore `application/problem+json` must
+/// be ranked strictly higher than `application/json`.
+fn prefers_problem_details(accept: &str) -> bool {
+    let quality = |media_type: &str| {
+        accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                if !parts.next()?.eq_ignore_ascii_case(media_type) {
+                    return None;
+                }
+                let q = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                Some(q)
+            })
+            .fold(0.0_f32, f32::max)
+    };
+    quality(PROBLEM_JSON) > quality("application/json")
+}
+
+/// JWT claim listing the groups of the caller.
+const GROUPS_CLAIM: &str = "cognito:groups";
+/// Header identifying the caller of the standalone server, if trusted.
+const PRINCIPAL_HEADER: &str = "x-bna-principal";
+/// Header listing the groups of the caller of the standalone server, if trusted.
+const GROUPS_HEADER: &str = "x-bna-groups";
+/// Group of the administrators, allowed to access the personal data.
+const ADMIN_GROUP: &str = "admin";
+
+/// Parses the groups of a JWT claim.
+///
+/// API Gateway flattens the array claims, e.g. `[admin reviewer]`.
+fn parse_groups(value: &str) -> Vec<String> {
+    value
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|g| !g.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 #[cfg(test)]
@@ -472,6 +970,92 @@ mod tests {
     use lambda_http::RequestExt;
     use std::collections::HashMap;
 
+    #[test]
+    fn test_prefers_problem_details() {
+        assert!(prefers_problem_details("application/problem+json"));
+        assert!(prefers_problem_details(
+            "application/json;q=0.5, application/problem+json"
+        ));
+        assert!(!prefers_problem_details("application/json"));
+        assert!(!prefers_problem_details("*/*"));
+        assert!(!prefers_problem_details(
+            "application/problem+json, application/json"
+        ));
+        assert!(!prefers_problem_details("application/problem+json;q=0"));
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        assert_eq!(parse_groups("[admin reviewer]"), vec!["admin", "reviewer"]);
+        assert_eq!(parse_groups("admin,reviewer"), vec!["admin", "reviewer"]);
+        assert!(parse_groups("[]").is_empty());
+    }
+
+    #[test]
+    fn test_uncovered_country_is_unprocessable() {
+        let error = api_error(ExecutionError::UncoveredCountry(
+            None,
+            "/cities".into(),
+            "Atlantis".into(),
+        ));
+        assert_eq!(error.status(), "422");
+    }
+
+    #[test]
+    fn test_validation_reports_every_attribute() {
+        let errors = APIErrors::from(ExecutionError::Validation(APIErrors::new(&[
+            APIError::with_pointer(None, "/email", "the email address is invalid"),
+            APIError::with_pointer(None, "/consent", "the consent is required"),
+        ])));
+        assert_eq!(errors.errors.len(), 2);
+        assert_eq!(errors.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_unauthorized_is_401() {
+        let error = api_error(ExecutionError::Unauthorized(
+            None,
+            "/cities/submissions/1/approve".into(),
+            "the reviewer must be authenticated".into(),
+        ));
+        assert_eq!(error.status(), "401");
+    }
+
+    #[test]
+    fn test_problem_type_by_error_kind() {
+        assert_eq!(
+            problem_type(StatusCode::NOT_FOUND),
+            "https://api.bna.peopleforbikes.org/problems/not-found"
+        );
+        assert_eq!(
+            problem_type(StatusCode::CONFLICT),
+            "https://api.bna.peopleforbikes.org/problems/conflict"
+        );
+        assert_eq!(
+            problem_type(StatusCode::INTERNAL_SERVER_ERROR),
+            PROBLEM_TYPE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_database_carries_request_context() {
+        let ctx = Context::new(Some("rid".into()), "/cities".into());
+        // The context is captured when the error is raised, not when it is rendered.
+        let error = REQUEST_CONTEXT
+            .scope(ctx, async {
+                ExecutionError::from(DbErr::ConnectionAcquire(sea_orm::ConnAcquireErr::Timeout))
+            })
+            .await;
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let errors: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(errors["errors"][0]["id"], "rid");
+    }
+
     #[test]
     fn test_paginatron_with_different_page_size() {
         let paginatron = Paginatron::new(
@@ -1,21 +1,21 @@
+use axum::{middleware, routing::get};
 use lambda_http::{run, tracing, Error};
-use lambdas::core::resource::{
-    cities, pipelines, price, ratings, reports,
-    schema::{APIError, APIErrorSource, APIErrors, OrderDirection},
-    system, usstates,
+use lambdas::core::resource::system;
+use lambdas::{
+    core::{
+        config::{self, Config},
+        cors, http_cache, metrics, openapi, response_cache, server, telemetry,
+    },
+    with_request_context,
 };
-use std::{
-    env::{self, set_var},
-    fs,
+use std::{env::set_var, path::Path};
+use tower_http::{
+    compression::CompressionLayer,
+    request_id::{PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
 };
-use tower_http::trace::TraceLayer;
 use tracing::{debug, info};
-use utoipa::{
-    openapi::{Components, ContactBuilder, Info, OpenApiBuilder, Server, Tag},
-    schema,
-};
-use utoipa_axum::router::OpenApiRouter;
-use utoipa_swagger_ui::SwaggerUi;
+use utoipa_swagger_ui::{SwaggerUi, Url};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -27,155 +27,86 @@ async fn main() -> Result<(), Error> {
     // i.e with: `GET /test-stage/todo/id/123` without: `GET /todo/id/123`
     set_var("AWS_LAMBDA_HTTP_IGNORE_STAGE_IN_PATH", "true");
 
-    // Check for user-defined log level.
-    let log_level = match env::var("BNA_API_LOG_LEVEL") {
-        Ok(v) => match v.to_ascii_lowercase().as_str() {
-            "debug" => tracing::Level::DEBUG,
-            _ => tracing::Level::INFO,
-        },
-        Err(_) => tracing::Level::INFO,
-    };
+    // Load and validate the configuration.
+    let config = Config::load()?;
 
     // required to enable CloudWatch error logging by the runtime
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        // disable printing the name of the module in every log line.
-        .with_target(false)
-        // disabling time is handy because CloudWatch will add the ingestion time.
-        .without_time()
-        .init();
-    debug!(loglevel= ?log_level);
+    let tracer_provider = telemetry::init(&config.log)?;
+    debug!(loglevel= ?config.log.level);
+    info!("Effective configuration:\n{config}");
+    let config = config::init(config);
 
-    // Define the OpenApi Specification.
-    let api = OpenApiBuilder::new()
-        .info(
-            Info::builder()
-                .title("BNA REST API")
-                .version("1.0.0")
-                .description(Some(
-                    "Provides a way to retrieve the BNA results programmatically.",
-                ))
-                .contact(Some(
-                    ContactBuilder::new()
-                        .name(Some("The BNA Mechanics team"))
-                        .url(Some("https://peopleforbikes.github.io/"))
-                        .build(),
-                ))
-                .build(),
+    // Create the app router, mounting each version of the API under its prefix, with
+    // its own specification.
+    let (app, specifications) = openapi::routes();
+    let app = app
+        .layer(middleware::from_fn(response_cache::track_cache_status))
+        .layer(middleware::from_fn(http_cache::conditional_get))
+        .layer(middleware::from_fn(with_request_context))
+        .layer(middleware::from_fn(metrics::track_metrics))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::make_span)
+                .on_response(telemetry::RecordStatus::default()),
         )
-        .servers(Some(vec![
-            Server::builder()
-                .description(Some("Local development API"))
-                .url("http://localhost:3000")
-                .build(),
-            Server::builder()
-                .description(Some("Staging API"))
-                .url("https://api.staging.bna.peopleforbikes.org")
-                .build(),
-            Server::builder()
-                .description(Some("Production API"))
-                .url("https://api.bna.peopleforbikes.org")
-                .build(),
-        ]))
-        .tags(Some(vec![
-            Tag::builder()
-                .name("city")
-                .description(Some("City API endpoints"))
-                .build(),
-            Tag::builder()
-                .name("pipeline")
-                .description(Some("Pipeline API endpoints"))
-                .build(),
-            Tag::builder()
-                .name("price")
-                .description(Some("Price API endpoints"))
-                .build(),
-            Tag::builder()
-                .name("rating")
-                .description(Some("Rating API endpoints"))
-                .build(),
-            Tag::builder()
-                .name("reports")
-                .description(Some("Reports API endpoints"))
-                .build(),
-            Tag::builder()
-                .name("usstate")
-                .description(Some("US State API endpoints"))
-                .build(),
-        ]))
-        .components(Some(
-            Components::builder()
-                .schema(
-                    "APIError",
-                    schema!(
-                        #[inline]
-                        APIError
-                    ),
-                )
-                .schema(
-                    "APIErrorSource",
-                    schema!(
-                        #[inline]
-                        APIErrorSource
-                    ),
-                )
-                .schema(
-                    "APIErrors",
-                    schema!(
-                        #[inline]
-                        APIErrors
-                    ),
-                )
-                .schema(
-                    "OrderDirection",
-                    schema!(
-                        #[inline]
-                        OrderDirection
-                    ),
-                )
-                .build(),
-        ))
-        .build();
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(telemetry::MakeApiRequestId))
+        .layer(middleware::from_fn(telemetry::prefer_gateway_request_id));
 
-    // Create the app router.
-    let (app, api) = OpenApiRouter::with_openapi(api)
-        .merge(cities::endpoint::routes())
-        .merge(pipelines::endpoint::routes())
-        .merge(price::endpoint::routes())
-        .merge(ratings::endpoint::routes())
-        .merge(reports::endpoint::routes())
-        .merge(system::endpoint::routes())
-        .merge(usstates::endpoint::routes())
-        .layer(TraceLayer::new_for_http())
-        .split_for_parts();
-
-    // Write the specification file to disk.
-    if env::var("BNA_API_GENERATE_ONLY")
-        .ok()
-        .is_some_and(|v| v == *"1")
-    {
-        info!("Regenerating the OpenAPI specification file.");
-        let _ = fs::write("./openapi-3.1.yaml", api.to_yaml().unwrap());
+    // Write the specification files to disk.
+    if config.features.generate_only {
+        info!("Regenerating the OpenAPI specification files.");
+        openapi::write_specifications(Path::new("."), &specifications)?;
         return Ok(());
     }
 
     // Add the Swagger UI.
-    let app = app.merge(SwaggerUi::new("/swagger-ui").url("/apidoc/openapi.json", api));
+    let app = if config.features.swagger_ui {
+        let swagger_ui = specifications.into_iter().fold(
+            SwaggerUi::new("/swagger-ui"),
+            |swagger_ui, (version, api)| {
+                swagger_ui.url(Url::new(version.name(), version.specification_url()), api)
+            },
+        );
+        app.merge(swagger_ui)
+    } else {
+        app
+    };
+
+    // Expose the metrics to the scrapers in standalone mode.
+    let app = if config.features.standalone {
+        app.route("/metrics", get(system::endpoint::get_metrics))
+    } else {
+        app
+    };
 
-    // Lookup for the  standalone flag.
-    let standalone = env::var("BNA_API_STANDALONE")
-        .ok()
-        .is_some_and(|v| v == *"1");
+    // Compress the responses, including the streamed reports, as negotiated by the
+    // clients.
+    let app = if config.features.compression {
+        app.layer(CompressionLayer::new().gzip(true).br(true).zstd(true))
+    } else {
+        app
+    };
+
+    // Allow the browser front-ends hosted on other origins.
+    let app = match cors::cors_layer(&config.cors) {
+        Some(cors) => app.layer(cors),
+        None => app,
+    };
 
     // Start the server in standalone mode or in lambda_http mode.
-    if standalone {
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
+    let result = if config.features.standalone {
+        server::serve(app, &config.server)
             .await
-            .unwrap();
-        tracing::info!("listening on {}", listener.local_addr().unwrap());
-        axum::serve(listener, app).await.unwrap();
-        Ok(())
+            .map_err(Error::from)
     } else {
+        metrics::enable_emf();
         run(app).await
+    };
+
+    // Flush the pending spans.
+    if let Some(provider) = tracer_provider {
+        provider.shutdown()?;
     }
+    result
 }
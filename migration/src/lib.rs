@@ -4,6 +4,10 @@ mod m20220101_000001_main;
 mod m20231010_232527_city_submission;
 mod m20240202_004130_brokenspoke_analyzer_pipeline;
 mod m20250529_151932_measure;
+mod m20261019_090000_bna_pipeline_timeout;
+mod m20261019_100000_summary_pipeline;
+mod m20261019_110000_webhook;
+mod m20261019_120000_submission_review;
 
 pub struct Migrator;
 
@@ -15,6 +19,10 @@ impl MigratorTrait for Migrator {
             Box::new(m20231010_232527_city_submission::Migration),
             Box::new(m20240202_004130_brokenspoke_analyzer_pipeline::Migration),
             Box::new(m20250529_151932_measure::Migration),
+            Box::new(m20261019_090000_bna_pipeline_timeout::Migration),
+            Box::new(m20261019_100000_summary_pipeline::Migration),
+            Box::new(m20261019_110000_webhook::Migration),
+            Box::new(m20261019_120000_submission_review::Migration),
         ]
     }
 }
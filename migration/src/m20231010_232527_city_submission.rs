@@ -82,7 +82,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(Iden)]
-enum Submission {
+pub enum Submission {
     Table,
     Id,
     FirstName,
@@ -0,0 +1,72 @@
+use crate::m20240202_004130_brokenspoke_analyzer_pipeline::BNAPipelineStatus;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{string_null, timestamp_with_time_zone_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add the terminal statuses for the pipelines which did not complete.
+        let insert_statuses = Query::insert()
+            .into_table(BNAPipelineStatus::Table)
+            .columns([BNAPipelineStatus::Status])
+            .values_panic(["Failed".into()])
+            .values_panic(["TimedOut".into()])
+            .to_owned();
+        manager.exec_stmt(insert_statuses).await?;
+
+        // Keep track of the last update and of the reason of a status change.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BNAPipeline::Table)
+                    .add_column(string_null(BNAPipeline::StatusReason))
+                    .add_column(timestamp_with_time_zone_null(BNAPipeline::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BNAPipeline::Table)
+                    .drop_column(BNAPipeline::StatusReason)
+                    .drop_column(BNAPipeline::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // The pipelines using the removed statuses are put back in the queue.
+        let reset_pipelines = Query::update()
+            .table(BNAPipeline::Table)
+            .value(BNAPipeline::Status, "Pending")
+            .and_where(Expr::col(BNAPipeline::Status).is_in(["Failed", "TimedOut"]))
+            .to_owned();
+        manager.exec_stmt(reset_pipelines).await?;
+        let delete_statuses = Query::delete()
+            .from_table(BNAPipelineStatus::Table)
+            .and_where(Expr::col(BNAPipelineStatus::Status).is_in(["Failed", "TimedOut"]))
+            .to_owned();
+        manager.exec_stmt(delete_statuses).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BNAPipeline {
+    Table,
+    Status,
+    /// Explanation of the last status change, i.e. why a pipeline timed out.
+    StatusReason,
+    /// Last time the pipeline was updated.
+    UpdatedAt,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::{prelude::*, schema::uuid_null};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep track of the pipeline which produced a rating.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Summary::Table)
+                    .add_column(uuid_null(Summary::PipelineId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-summary-pipeline_id")
+                    .from(Summary::Table, Summary::PipelineId)
+                    .to(BNAPipeline::Table, BNAPipeline::StateMachineId)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("summary_pipeline_id_idx")
+                    .table(Summary::Table)
+                    .col(Summary::PipelineId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("summary_pipeline_id_idx")
+                    .table(Summary::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-summary-pipeline_id")
+                    .table(Summary::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Summary::Table)
+                    .drop_column(Summary::PipelineId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Summary {
+    Table,
+    /// Identifier of the pipeline which produced the rating.
+    PipelineId,
+}
+
+#[derive(DeriveIden)]
+enum BNAPipeline {
+    Table,
+    StateMachineId,
+}
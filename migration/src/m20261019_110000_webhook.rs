@@ -0,0 +1,167 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{
+        boolean, integer, json_binary, string, string_null, timestamp_with_time_zone,
+        timestamp_with_time_zone_null, uuid,
+    },
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the webhook subscription table.
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookSubscription::Table)
+                    .if_not_exists()
+                    .col(uuid(WebhookSubscription::Id).primary_key())
+                    .col(string(WebhookSubscription::Url))
+                    .col(string(WebhookSubscription::Secret))
+                    .col(json_binary(WebhookSubscription::Events))
+                    .col(boolean(WebhookSubscription::Active).default(true))
+                    .col(
+                        timestamp_with_time_zone(WebhookSubscription::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(
+                        WebhookSubscription::UpdatedAt,
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create the delivery status table.
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveryStatus::Table)
+                    .if_not_exists()
+                    .col(string(WebhookDeliveryStatus::Status).primary_key())
+                    .to_owned(),
+            )
+            .await?;
+        let insert_delivery_statuses = Query::insert()
+            .into_table(WebhookDeliveryStatus::Table)
+            .columns([WebhookDeliveryStatus::Status])
+            .values_panic(["Pending".into()])
+            .values_panic(["Delivered".into()])
+            .values_panic(["Failed".into()])
+            .to_owned();
+        manager.exec_stmt(insert_delivery_statuses).await?;
+
+        // Create the outbox table, containing one entry per event and subscription.
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookOutbox::Table)
+                    .if_not_exists()
+                    .col(uuid(WebhookOutbox::Id).primary_key())
+                    .col(uuid(WebhookOutbox::SubscriptionId))
+                    .col(string(WebhookOutbox::EventType))
+                    .col(json_binary(WebhookOutbox::Payload))
+                    .col(string(WebhookOutbox::Status).default("Pending"))
+                    .col(integer(WebhookOutbox::Attempts).default(0))
+                    .col(
+                        timestamp_with_time_zone(WebhookOutbox::NextAttemptAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(string_null(WebhookOutbox::LastError))
+                    .col(
+                        timestamp_with_time_zone(WebhookOutbox::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone_null(WebhookOutbox::DeliveredAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(WebhookOutbox::Table, WebhookOutbox::SubscriptionId)
+                            .to(WebhookSubscription::Table, WebhookSubscription::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(WebhookOutbox::Table, WebhookOutbox::Status)
+                            .to(WebhookDeliveryStatus::Table, WebhookDeliveryStatus::Status),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .table(WebhookOutbox::Table)
+                    .col(WebhookOutbox::Status)
+                    .col(WebhookOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookOutbox::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveryStatus::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WebhookSubscription::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookSubscription {
+    Table,
+    /// Subscription identifier.
+    Id,
+    /// URL receiving the events.
+    Url,
+    /// Secret used to sign the deliveries.
+    Secret,
+    /// List of the event types the subscription is interested in.
+    Events,
+    /// Whether the events must be delivered.
+    Active,
+    /// Creation date.
+    CreatedAt,
+    /// Update date.
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveryStatus {
+    Table,
+    Status,
+}
+
+#[derive(DeriveIden)]
+enum WebhookOutbox {
+    Table,
+    /// Delivery identifier.
+    Id,
+    /// Subscription the event must be delivered to.
+    SubscriptionId,
+    /// Event type, i.e. `pipeline.completed`.
+    EventType,
+    /// Content of the event.
+    Payload,
+    /// Delivery status.
+    Status,
+    /// Number of delivery attempts.
+    Attempts,
+    /// Earliest time of the next delivery attempt.
+    NextAttemptAt,
+    /// Error of the last failed attempt.
+    LastError,
+    /// Creation date.
+    CreatedAt,
+    /// Delivery date.
+    DeliveredAt,
+}
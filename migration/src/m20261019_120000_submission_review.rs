@@ -0,0 +1,87 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{integer, pk_auto, string, string_null, timestamp_with_time_zone},
+};
+
+use crate::m20231010_232527_city_submission::{ApprovalStatus, Submission};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create the submission review table, keeping track of the decisions made on
+        // a submission.
+        manager
+            .create_table(
+                Table::create()
+                    .table(SubmissionReview::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SubmissionReview::Id))
+                    .col(integer(SubmissionReview::SubmissionId))
+                    .col(string(SubmissionReview::Reviewer))
+                    .col(string(SubmissionReview::PreviousStatus))
+                    .col(string(SubmissionReview::Status))
+                    .col(string_null(SubmissionReview::Note))
+                    .col(
+                        timestamp_with_time_zone(SubmissionReview::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(SubmissionReview::Table, SubmissionReview::SubmissionId)
+                            .to(Submission::Table, Submission::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(SubmissionReview::Table, SubmissionReview::PreviousStatus)
+                            .to(ApprovalStatus::Table, ApprovalStatus::Status),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(SubmissionReview::Table, SubmissionReview::Status)
+                            .to(ApprovalStatus::Table, ApprovalStatus::Status),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("submission_review_submission_id_idx")
+                    .table(SubmissionReview::Table)
+                    .col(SubmissionReview::SubmissionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SubmissionReview::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SubmissionReview {
+    Table,
+    /// Review identifier.
+    Id,
+    /// Submission being reviewed.
+    SubmissionId,
+    /// Identity of the reviewer.
+    Reviewer,
+    /// Status of the submission before the review.
+    PreviousStatus,
+    /// Status of the submission after the review.
+    Status,
+    /// Reviewer note, mandatory for the rejections.
+    Note,
+    /// Review date.
+    CreatedAt,
+}